@@ -0,0 +1,124 @@
+#include <Arduino.h>
+#include <Wire.h>
+#include "MS5837.h"
+
+#define MS5837_ADDRESS 0x76
+#define MS5837_RESET 0x1E
+#define MS5837_ADC_READ 0x00
+#define MS5837_PROM_READ 0xA0
+#define MS5837_CONVERT_D1_8192 0x4A
+#define MS5837_CONVERT_D2_8192 0x5A
+
+// Longest conversion time at 8192 oversampling, in milliseconds
+#define MS5837_CONVERSION_TIME 20
+
+MS5837::MS5837() {
+    state = Idle;
+    pressure_pa = 0;
+}
+
+bool MS5837::begin() {
+    Wire.begin();
+
+    Wire.beginTransmission(MS5837_ADDRESS);
+    Wire.write(MS5837_RESET);
+    if (Wire.endTransmission() != 0) {
+        return false;
+    }
+    delay(10);
+
+    for (uint8_t i = 0; i < 7; i++) {
+        Wire.beginTransmission(MS5837_ADDRESS);
+        Wire.write(MS5837_PROM_READ + i * 2);
+        Wire.endTransmission();
+
+        if (Wire.requestFrom(MS5837_ADDRESS, 2) != 2) {
+            return false;
+        }
+        prom[i] = (Wire.read() << 8) | Wire.read();
+    }
+
+    state = Idle;
+    return true;
+}
+
+bool MS5837::update() {
+    switch (state) {
+        case Idle: {
+            start_conversion(MS5837_CONVERT_D1_8192);
+            state = ConvertingPressure;
+            return false;
+        }
+        case ConvertingPressure: {
+            if (millis() - conversion_started < MS5837_CONVERSION_TIME) {
+                return false;
+            }
+            d1 = read_adc();
+            start_conversion(MS5837_CONVERT_D2_8192);
+            state = ConvertingTemperature;
+            return false;
+        }
+        case ConvertingTemperature: {
+            if (millis() - conversion_started < MS5837_CONVERSION_TIME) {
+                return false;
+            }
+            d2 = read_adc();
+            calculate();
+            state = Idle;
+            return true;
+        }
+    }
+    return false;
+}
+
+uint32_t MS5837::pressure() {
+    return pressure_pa < 0 ? 0 : (uint32_t) pressure_pa;
+}
+
+void MS5837::start_conversion(uint8_t command) {
+    Wire.beginTransmission(MS5837_ADDRESS);
+    Wire.write(command);
+    Wire.endTransmission();
+    conversion_started = millis();
+}
+
+uint32_t MS5837::read_adc() {
+    Wire.beginTransmission(MS5837_ADDRESS);
+    Wire.write(MS5837_ADC_READ);
+    Wire.endTransmission();
+
+    Wire.requestFrom(MS5837_ADDRESS, 3);
+    uint32_t value = 0;
+    value = Wire.read();
+    value = (value << 8) | Wire.read();
+    value = (value << 8) | Wire.read();
+    return value;
+}
+
+// First and second order compensation from the MS5837-30BA datasheet
+void MS5837::calculate() {
+    int32_t dT = d2 - (uint32_t) prom[5] * 256l;
+    int64_t sens = (int64_t) prom[1] * 32768l + ((int64_t) prom[3] * dT) / 256l;
+    int64_t off = (int64_t) prom[2] * 65536l + ((int64_t) prom[4] * dT) / 128l;
+    int32_t temp = 2000l + (int64_t) dT * prom[6] / 8388608LL;
+
+    int64_t off_i;
+    int64_t sens_i;
+    if (temp < 2000) {
+        off_i = 3 * (int64_t) (temp - 2000) * (temp - 2000) / 2;
+        sens_i = 5 * (int64_t) (temp - 2000) * (temp - 2000) / 8;
+        if (temp < -1500) {
+            off_i += 7 * (int64_t) (temp + 1500) * (temp + 1500);
+            sens_i += 4 * (int64_t) (temp + 1500) * (temp + 1500);
+        }
+    } else {
+        off_i = (int64_t) (temp - 2000) * (temp - 2000) / 16;
+        sens_i = 0;
+    }
+    off -= off_i;
+    sens -= sens_i;
+
+    // In tenths of a millibar, which are 10 pascals
+    int32_t p = (((int64_t) d1 * sens) / 2097152l - off) / 8192l;
+    pressure_pa = p * 10;
+}
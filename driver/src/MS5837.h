@@ -0,0 +1,38 @@
+#ifndef MS5837_h_INCLUDED
+#define MS5837_h_INCLUDED
+
+#include <stdint.h>
+
+// Reads the MS5837-30BA pressure sensor without blocking. Each reading takes
+// two conversions of about 20ms; `update` should be called every loop and
+// returns true when a new pressure is ready.
+class MS5837 {
+public:
+    MS5837();
+
+    bool begin();
+    bool update();
+
+    // In pascals
+    uint32_t pressure();
+
+private:
+    enum State {
+        Idle,
+        ConvertingPressure,
+        ConvertingTemperature,
+    };
+
+    void start_conversion(uint8_t command);
+    uint32_t read_adc();
+    void calculate();
+
+    uint16_t prom[8];
+    uint32_t d1;
+    uint32_t d2;
+    int32_t pressure_pa;
+    State state;
+    unsigned long conversion_started;
+};
+
+#endif // MS5837_h_INCLUDED
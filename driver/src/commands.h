@@ -7,6 +7,7 @@
 #define COMMAND_CONTROL_MOTOR 0x10
 #define COMMAND_LIGHTS_ON 0x31
 #define COMMAND_LIGHTS_OFF 0x30
+#define COMMAND_SET_LIGHT 0x32
 #define COMMAND_MASTER_ON 0x40
 #define COMMAND_MASTER_OFF 0x43
 #define COMMAND_CONTROL_SERVO 0x66
@@ -16,6 +17,7 @@ enum Commands {
   ControlMotor = COMMAND_CONTROL_MOTOR,
   LightsOn = COMMAND_LIGHTS_ON,
   LightsOff = COMMAND_LIGHTS_OFF,
+  SetLight = COMMAND_SET_LIGHT,
   MasterOn = COMMAND_MASTER_ON,
   MasterOff = COMMAND_MASTER_OFF,
   ControlServo = COMMAND_CONTROL_SERVO,
@@ -27,6 +29,7 @@ uint8_t get_command_length(Commands command) {
     case ControlMotor: return 3;
     case LightsOn: return 0;
     case LightsOff: return 0;
+    case SetLight: return 2;
     case MasterOn: return 0;
     case MasterOff: return 0;
     case ControlServo: return 3;
@@ -40,6 +43,7 @@ bool is_valid_command(uint8_t id) {
     case ControlMotor: return true;
     case LightsOn: return true;
     case LightsOff: return true;
+    case SetLight: return true;
     case MasterOn: return true;
     case MasterOff: return true;
     case ControlServo: return true;
@@ -3,11 +3,15 @@
 #include <Servo.h>
 #include <Adafruit_Sensor.h>
 #include <BNO055.h>
+#include "MS5837.h"
 #include "commands.h"
 #include "respond.h"
 #include "main.h"
 
 #define LIGHTS_RELAY_PIN 13
+// Dimmable LED channels, on pins that keep their PWM while the servos run
+#define NUM_LIGHTS 2
+const uint8_t light_pins[NUM_LIGHTS] = {3, 11};
 
 #define MAX_CONTROL_SIGNAL 1100
 #define MIN_CONTROL_SIGNAL 1900
@@ -24,7 +28,7 @@
 #endif
 
 #define NUM_MOTORS 4
-#define NUM_SERVOS 2
+#define NUM_SERVOS 4
 
 Commands command_received;
 uint8_t buffer[4];
@@ -40,6 +44,9 @@ bool robot_is_on;
 BNO055 bno_compass = BNO055(55);
 bool compass_enabled;
 
+MS5837 depth_sensor;
+bool depth_enabled;
+
 void setup()
 {
   Serial.begin(115200);
@@ -50,6 +57,10 @@ void setup()
   }
   master_on();
   bno_compass.setExtCrystalUse(true);
+  depth_enabled = depth_sensor.begin();
+  if(!depth_enabled) {
+      say_pressure_disabled();
+  }
 }
 
 void loop()
@@ -109,6 +120,7 @@ void loop()
   }
 
   update_compass();
+  update_depth();
 }
 
 void handle_command(Commands command, uint8_t *buffer)
@@ -145,6 +157,15 @@ void handle_command(Commands command, uint8_t *buffer)
       say_lights_off();
       break;
     }
+    case SetLight: {
+      uint8_t light_id = buffer[0];
+      if (light_id < NUM_LIGHTS)
+      {
+        analogWrite(light_pins[light_id], buffer[1]);
+        say_light(light_id, buffer[1]);
+      }
+      break;
+    }
     case MasterOn: {
       // We should never reach here
       break;
@@ -155,7 +176,7 @@ void handle_command(Commands command, uint8_t *buffer)
     }
     case ControlServo: {
       uint8_t servo_id = buffer[0];
-      if (servo_id < NUM_MOTORS)
+      if (servo_id < NUM_SERVOS)
       {
         int16_t microseconds = (((int16_t) buffer[1]) << 8) | buffer[2];
         servos[servo_id].writeMicroseconds(microseconds);
@@ -192,6 +213,13 @@ void servos_reset() {
     }
 }
 
+void lights_off() {
+    for (uint8_t i = 0; i < NUM_LIGHTS; i++) {
+        analogWrite(light_pins[i], 0);
+        say_light(i, 0);
+    }
+}
+
 void master_on() {
   robot_is_on = true;
 
@@ -199,6 +227,10 @@ void master_on() {
   // TODO: Ask if the lights should default to on
   digitalWrite(LIGHTS_RELAY_PIN, LOW);
   say_lights_off();
+  for (uint8_t i = 0; i < NUM_LIGHTS; i++) {
+    pinMode(light_pins[i], OUTPUT);
+  }
+  lights_off();
 
   /* ## Turn motors on ## */
   motors[0].attach(5);
@@ -209,6 +241,9 @@ void master_on() {
 
   servos[0].attach(9);
   servos[1].attach(10);
+  // Gripper jaw and wrist
+  servos[2].attach(12);
+  servos[3].attach(A0);
   servos_reset();
 
   i2c_scan();
@@ -224,6 +259,7 @@ void master_off() {
 
   digitalWrite(LIGHTS_RELAY_PIN, LOW);
   say_lights_off();
+  lights_off();
 
   motors_stop();
   servos_reset();
@@ -244,3 +280,10 @@ void update_compass() {
     say_compass_orientation(numbers[0], numbers[1], numbers[2]);
 }
 
+void update_depth() {
+    if (!depth_enabled) return;
+
+    if (depth_sensor.update()) {
+        say_pressure(depth_sensor.pressure());
+    }
+}
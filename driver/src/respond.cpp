@@ -5,8 +5,11 @@
 #define RESPONSE_MOTOR 0x10
 #define RESPONSE_COMPASS_ORIENTATION 0x20
 #define RESPONSE_COMPASS_DISABLED 0x21
+#define RESPONSE_PRESSURE 0x50
+#define RESPONSE_PRESSURE_DISABLED 0x51
 #define RESPONSE_LIGHTS_ON 0x31
 #define RESPONSE_LIGHTS_OFF 0x30
+#define RESPONSE_LIGHT 0x32
 #define RESPONSE_MASTER_ON 0x40
 #define RESPONSE_MASTER_OFF 0x43
 #define RESPONSE_SERVO 0x66
@@ -40,6 +43,21 @@ void say_compass_disabled() {
     Serial.write(RESPONSE_COMPASS_DISABLED);
 }
 
+void say_pressure(uint32_t pascals) {
+    uint8_t buf[] = {
+      RESPONSE_PRESSURE,
+      (uint8_t)((pascals>>24)&0xff),
+      (uint8_t)((pascals>>16)&0xff),
+      (uint8_t)((pascals>>8)&0xff),
+      (uint8_t)((pascals)&0xff)
+    };
+    Serial.write(buf, 5);
+}
+
+void say_pressure_disabled() {
+    Serial.write(RESPONSE_PRESSURE_DISABLED);
+}
+
 void say_lights_on() {
     Serial.write(RESPONSE_LIGHTS_ON);
 }
@@ -48,6 +66,15 @@ void say_lights_off() {
     Serial.write(RESPONSE_LIGHTS_OFF);
 }
 
+void say_light(uint8_t id, uint8_t level) {
+    uint8_t buf[] = {
+        RESPONSE_LIGHT,
+        id,
+        level
+        };
+    Serial.write(buf, 3);
+}
+
 void say_master_on() {
     Serial.write(RESPONSE_MASTER_ON);
 }
@@ -4,8 +4,11 @@
 void say_motor(uint8_t id, int16_t thrust); 
 void say_compass_orientation(int16_t x, int16_t y, int16_t z);
 void say_compass_disabled(); 
+void say_pressure(uint32_t pascals);
+void say_pressure_disabled();
 void say_lights_on();
 void say_lights_off();
+void say_light(uint8_t id, uint8_t level);
 void say_master_on();
 void say_master_off();
 void say_servo(uint8_t id, int16_t microseconds);
@@ -1,7 +1,7 @@
 #ifndef version_h_INCLUDED
 #define version_h_INCLUDED
 
-#define DRIVER_VERSION 0x0103
+#define DRIVER_VERSION 0x0104
 
 #endif // version_h_INCLUDED
 
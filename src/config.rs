@@ -1,24 +1,1015 @@
+use input::Button;
+use input::roles::Role;
+use input::shaping::Shaping;
+use control::binding::Action;
+use control::manipulator::Mode;
+use rov::RovCommand;
+
+/// Air pressure at sea level, in pascals.
+pub const STANDARD_ATMOSPHERE: f32 = 101325.0;
 
 #[derive(Deserialize, Clone, Debug, Default)]
 pub struct Config {
     pub control: Controls,
+    #[serde(default)]
+    pub depth: Depth,
+    #[serde(default)]
+    pub bench: Bench,
+    #[serde(default)]
+    pub timing: Timing,
+    #[serde(default)]
+    pub staleness: Staleness,
+    #[serde(default)]
+    pub reconcile: Reconcile,
 }
 
-#[derive(Deserialize, Clone, Debug, Default)]
+/// How long the ROV may take to echo a command before the difference is shown on the HUD.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Reconcile {
+    /// In seconds
+    pub after: f64,
+}
+
+/// Seconds after which what the ROV reported is shown as out of date. The compass and depth
+/// sensor report all the time, while the rest only echo commands, so are never out of date
+/// unless given a limit here. Master power is only reported when it switches, so with a limit it
+/// is out of date only until the ROV first reports it.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Staleness {
+    pub motors: Option<f64>,
+    pub servos: Option<f64>,
+    pub lights: Option<f64>,
+    pub master: Option<f64>,
+    pub compass: Option<f64>,
+    pub pressure: Option<f64>,
+}
+
+/// How often the vehicle is controlled and the window is redrawn, in hertz. Control runs at its
+/// own rate whether or not the window is drawn.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Timing {
+    pub control_rate: f64,
+    pub frame_rate: f64,
+}
+
+/// How the depth is worked out from the pressure sensor.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Depth {
+    pub water: Water,
+    /// Pressure at the surface, in pascals
+    pub surface_pressure: f32,
+}
+
+/// Pulses used by the motor bench. The thrust is sent without the motors' calibration, so that
+/// wiring mistakes show up.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Bench {
+    /// In -1..1
+    pub thrust: f32,
+    /// In seconds
+    pub pulse_time: f64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Water {
+    Fresh,
+    Salt,
+}
+
+impl Water {
+    /// In kg/m³
+    pub fn density(self) -> f32 {
+        match self {
+            Water::Fresh => 997.0,
+            Water::Salt => 1025.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Controls {
+    #[serde(default)]
     pub servo_pan: Servo,
+    #[serde(default)]
     pub servo_tilt: Servo,
+    #[serde(default)]
+    pub roles: Roles,
+    #[serde(default)]
+    pub shaping: Shaping,
+    #[serde(default)]
+    pub motors: Vec<Motor>,
+    #[serde(default)]
+    pub gain: Gain,
+    #[serde(default = "Hold::heading")]
+    pub heading_hold: Hold,
+    #[serde(default = "Hold::depth")]
+    pub depth_hold: Hold,
+    #[serde(default)]
+    pub stabiliser: Stabiliser,
+    #[serde(default)]
+    pub arming: Arming,
+    #[serde(default = "Binding::defaults")]
+    pub bindings: Vec<Binding>,
+    #[serde(default)]
+    pub manipulator: Option<Manipulator>,
+    #[serde(default)]
+    pub lights: Vec<Light>,
+    #[serde(default)]
+    pub sequences: Vec<Sequence>,
+    #[serde(default)]
+    pub power: Option<Power>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    #[serde(default = "Controls::default_profile_button")]
+    pub profile_button: Button,
+    #[serde(default)]
+    pub trim: Trim,
+    #[serde(default)]
+    pub cruise: Option<Cruise>,
+}
+
+impl Controls {
+    /// These settings with a profile's in place of the ones it sets.
+    pub fn with_profile(&self, profile: &Profile) -> Controls {
+        let mut controls = self.clone();
+        if let Some(ref gain) = profile.gain {
+            controls.gain = gain.clone();
+        }
+        if let Some(ref shaping) = profile.shaping {
+            controls.shaping = shaping.clone();
+        }
+        if let Some(ref bindings) = profile.bindings {
+            controls.bindings = bindings.clone();
+        }
+        if let Some(ref offsets) = profile.trim {
+            controls.trim.offsets = offsets.clone();
+        }
+        controls
+    }
+
+    fn default_profile_button() -> Button {
+        Button::Select
+    }
+
+    /// The settings for a motor, falling back to the defaults if it has no entry.
+    pub fn motor(&self, id: u8) -> Motor {
+        self.motors
+            .iter()
+            .find(|motor| motor.id == id)
+            .cloned()
+            .unwrap_or(Motor { id: id, ..Motor::default() })
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
 pub struct Servo {
     pub speed: f64,
+    /// Mechanical limits in microseconds
+    pub min: i16,
+    pub max: i16,
+    /// Offset of the centre position in microseconds
+    pub trim: i16,
+    pub recenter: Button,
+    pub presets: Vec<ServoPreset>,
+}
+
+impl Servo {
+    pub fn build(&self,
+                 id: u8,
+                 increase_button: Button,
+                 decrease_button: Button)
+                 -> ::control::servo::Servo {
+        let mut servo =
+            ::control::servo::Servo::new(id, increase_button, decrease_button, self.speed)
+                .calibrate(self.min, self.max, self.trim)
+                .recenter_button(self.recenter);
+        for preset in self.presets.iter() {
+            servo = servo.preset(preset.button, preset.position);
+        }
+        servo
+    }
+}
+
+/// A claw on the servo channels after the camera's, fitted when this section is present.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Manipulator {
+    pub mode: Mode,
+    pub jaw: u8,
+    pub wrist: Option<u8>,
+    /// Jaw pulse widths in microseconds, as positions or as full speed depending on the mode
+    pub open: i16,
+    pub closed: i16,
+    /// Share of the closing travel or drive that may be used, in 0..1
+    pub grip: f32,
+    pub speed: f64,
+    pub open_button: Button,
+    pub close_button: Button,
+    pub rotate_left: Button,
+    pub rotate_right: Button,
+    pub role: Role,
+}
+
+impl Manipulator {
+    pub fn build(&self) -> ::control::manipulator::Manipulator {
+        let manipulator = ::control::manipulator::Manipulator::new(self.mode,
+                                                                   self.jaw,
+                                                                   self.open,
+                                                                   self.closed,
+                                                                   self.speed)
+            .buttons(self.open_button, self.close_button)
+            .grip(self.grip);
+        match self.wrist {
+            Some(id) => manipulator.wrist(id, self.rotate_left, self.rotate_right),
+            None => manipulator,
+        }
+    }
+}
+
+/// A named position that the servo moves to when the button is pressed, e.g. "Look down".
+#[derive(Deserialize, Clone, Debug)]
+pub struct ServoPreset {
+    pub name: String,
+    pub button: Button,
+    /// In microseconds
+    pub position: i16,
+}
+
+/// Slew rates of zero or less would leave a motor that never moves or never stops, so they are
+/// refused as the config loads.
+fn positive<'de, D>(deserializer: D) -> ::std::result::Result<f32, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    use serde::Deserialize;
+    use serde::de::Error;
+    let rate = f32::deserialize(deserializer)?;
+    if rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err(D::Error::custom(format!("expected a rate above zero, found {}", rate)))
+    }
+}
+
+fn positive_option<'de, D>(deserializer: D) -> ::std::result::Result<Option<f32>, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    positive(deserializer).map(Some)
+}
+
+/// Master power only changes through arming, and the motors only through the thrusters, so
+/// buttons can't bypass either. Such commands are refused as the config loads.
+fn bindable<'de, D>(deserializer: D) -> ::std::result::Result<RovCommand, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    use serde::Deserialize;
+    use serde::de::Error;
+    let command = RovCommand::deserialize(deserializer)?;
+    match command {
+        RovCommand::MasterOn | RovCommand::MasterOff => {
+            Err(D::Error::custom("master power can only be switched by arming"))
+        }
+        RovCommand::ControlMotor { .. } => {
+            Err(D::Error::custom("motors can only be driven through the thrusters"))
+        }
+        command => Ok(command),
+    }
+}
+
+fn bindable_option<'de, D>(deserializer: D) -> ::std::result::Result<Option<RovCommand>, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    bindable(deserializer).map(Some)
+}
+
+fn bindable_list<'de, D>(deserializer: D) -> ::std::result::Result<Vec<RovCommand>, D::Error>
+    where D: ::serde::Deserializer<'de>
+{
+    use serde::Deserialize;
+    #[derive(Deserialize)]
+    struct Bindable(#[serde(deserialize_with = "bindable")] RovCommand);
+    let commands = Vec::<Bindable>::deserialize(deserializer)?;
+    Ok(commands.into_iter().map(|Bindable(command)| command).collect())
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Motor {
+    pub id: u8,
+    /// Full throttles per second that the thrust may rise by.
+    #[serde(deserialize_with = "positive")]
+    pub acceleration: f32,
+    /// Full throttles per second that the thrust may fall back toward zero by.
+    #[serde(deserialize_with = "positive")]
+    pub deceleration: f32,
+    pub reversed: bool,
+    pub forward_scale: f32,
+    pub reverse_scale: f32,
+    /// Fraction of the range around the middle in which the ESC doesn't spin the motor
+    pub deadband: f32,
+    /// Where the motor sits from the vehicle's centre, as forward, left and up. Motors 0 to 3
+    /// fall back to the built-in layout; other motors are only used if they have a direction.
+    pub position: Option<[f32; 3]>,
+    /// Which way the motor pushes the vehicle
+    pub direction: Option<[f32; 3]>,
+}
+
+impl Motor {
+    pub fn calibration(&self) -> ::control::motor::Calibration {
+        ::control::motor::Calibration {
+            reversed: self.reversed,
+            forward_scale: self.forward_scale,
+            reverse_scale: self.reverse_scale,
+            deadband: self.deadband,
+        }
+    }
+}
+
+/// A named set of settings for one kind of work, e.g. transit or docking, that the pilot can
+/// switch to during a dive. Anything left out keeps the setting from `[control]`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub gain: Option<Gain>,
+    #[serde(default)]
+    pub shaping: Option<Shaping>,
+    /// Replace every motor's slew limits, in full throttles per second
+    #[serde(default, deserialize_with = "positive_option")]
+    pub acceleration: Option<f32>,
+    #[serde(default, deserialize_with = "positive_option")]
+    pub deceleration: Option<f32>,
+    /// Switched on as the profile is chosen, with the others switched off
+    #[serde(default)]
+    pub autopilots: Option<Vec<Autopilot>>,
+    #[serde(default)]
+    pub bindings: Option<Vec<Binding>>,
+    /// Saved here while the profile is chosen, so each profile keeps its own trim
+    #[serde(default)]
+    pub trim: Option<TrimOffsets>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Autopilot {
+    HeadingHold,
+    DepthHold,
+    Stabiliser,
+}
+
+/// Offsets added to the demand to make up for buoyancy and balance, each a share of full demand.
+/// Adjusting them during a dive saves them back to the config file.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Trim {
+    pub offsets: TrimOffsets,
+    /// How far one press moves the trim
+    pub step: f32,
+    /// Buttons that raise and lower each trim. None are set up unless given here.
+    pub heave_buttons: Option<(Button, Button)>,
+    pub pitch_buttons: Option<(Button, Button)>,
+    pub roll_buttons: Option<(Button, Button)>,
+}
+
+impl Trim {
+    /// Roll and pitch trim are left out when no thruster can turn the vehicle that way.
+    pub fn build(&self, roll: bool, pitch: bool) -> ::control::trim::Trim {
+        use control::thrusters::Wrench;
+        if !roll && (self.roll_buttons.is_some() || self.offsets.roll != 0.0) {
+            warn!("Roll trim set up, but no thruster can roll the vehicle");
+        }
+        if !pitch && (self.pitch_buttons.is_some() || self.offsets.pitch != 0.0) {
+            warn!("Pitch trim set up, but no thruster can pitch the vehicle");
+        }
+        let offsets = &self.offsets;
+        let offset = Wrench {
+            heave: offsets.heave,
+            pitch: if pitch { offsets.pitch } else { 0.0 },
+            roll: if roll { offsets.roll } else { 0.0 },
+            ..Wrench::default()
+        };
+        let mut trim = ::control::trim::Trim::new(offset, self.step);
+        let axes = [(self.heave_buttons, true, Wrench { heave: 1.0, ..Wrench::default() }),
+                    (self.pitch_buttons, pitch, Wrench { pitch: 1.0, ..Wrench::default() }),
+                    (self.roll_buttons, roll, Wrench { roll: 1.0, ..Wrench::default() })];
+        for &(buttons, usable, direction) in axes.iter() {
+            if let (Some((increase, decrease)), true) = (buttons, usable) {
+                trim = trim.buttons(increase, decrease, direction);
+            }
+        }
+        trim
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct TrimOffsets {
+    pub heave: f32,
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+impl TrimOffsets {
+    pub fn from_offset(offset: &::control::thrusters::Wrench) -> TrimOffsets {
+        TrimOffsets {
+            heave: offset.heave,
+            pitch: offset.pitch,
+            roll: offset.roll,
+        }
+    }
+}
+
+/// Latches the forward demand when the button is pressed. See `control::cruise::Cruise`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Cruise {
+    pub button: Button,
+}
+
+/// The most the motors may draw together. See `control::power::PowerBudget` for how the draw is
+/// estimated with and without a current curve.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Power {
+    pub budget: f32,
+    /// Amps one motor draws at evenly spaced thrusts from zero to full
+    #[serde(default)]
+    pub curve: Vec<f32>,
+}
+
+impl Power {
+    pub fn build(&self) -> ::control::power::PowerBudget {
+        ::control::power::PowerBudget::new(self.budget, self.curve.clone())
+    }
+}
+
+/// Profiles that scale the pilot's demand, cycled through with a button.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Gain {
+    pub button: Button,
+    pub modes: Vec<GainMode>,
+    /// Index of the mode to start in
+    pub start: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GainMode {
+    pub name: String,
+    pub translation: f32,
+    pub rotation: f32,
+}
+
+/// Gains for a PID loop, and the largest demand it may add.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub max_output: f32,
+}
+
+impl Pid {
+    pub fn build(&self) -> ::control::pid::Pid {
+        ::control::pid::Pid::new(self.kp, self.ki, self.kd, self.max_output)
+    }
+
+    pub fn from_loop(pid: &::control::pid::Pid) -> Pid {
+        Pid {
+            kp: pid.kp,
+            ki: pid.ki,
+            kd: pid.kd,
+            max_output: pid.output_limit,
+        }
+    }
+}
+
+/// Keeps a measurement while the stick that controls it is centred. The heading hold's gains act
+/// on the error in degrees, and the depth hold's on the error in metres.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Hold {
+    pub button: Button,
+    pub pid: Pid,
+}
+
+/// Sends commands when a button is used. `off` is optional; see `control::binding::Binding`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Binding {
+    pub button: Button,
+    pub action: Action,
+    #[serde(deserialize_with = "bindable")]
+    pub on: RovCommand,
+    #[serde(default, deserialize_with = "bindable_option")]
+    pub off: Option<RovCommand>,
+    #[serde(default = "Binding::default_role")]
+    pub role: Role,
+    /// Seconds a long press lasts
+    #[serde(default = "Binding::default_hold_time")]
+    pub hold_time: f64,
+    /// Most seconds between the presses of a double tap
+    #[serde(default = "Binding::default_double_tap_time")]
+    pub double_tap_time: f64,
+}
+
+impl Binding {
+    pub fn build(&self) -> ::control::binding::Binding {
+        let off = self.off.clone();
+        ::control::binding::Binding::new(self.button, self.action, self.on.clone(), off)
+            .timing(self.hold_time, self.double_tap_time)
+    }
+
+    fn defaults() -> Vec<Binding> {
+        vec![Binding {
+                 button: Button::North,
+                 action: Action::Toggle,
+                 on: RovCommand::LightsOn,
+                 off: Some(RovCommand::LightsOff),
+                 role: Binding::default_role(),
+                 hold_time: Binding::default_hold_time(),
+                 double_tap_time: Binding::default_double_tap_time(),
+             }]
+    }
+
+    fn default_role() -> Role {
+        Role::CoPilot
+    }
+
+    fn default_hold_time() -> f64 {
+        1.0
+    }
+
+    fn default_double_tap_time() -> f64 {
+        0.3
+    }
+}
+
+/// A dimmable light channel. Needs firmware that understands `SetLight`; older firmware only has
+/// the relay, which a binding can switch with `LightsOn` and `LightsOff`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Light {
+    pub id: u8,
+    pub brighter: Button,
+    pub dimmer: Button,
+    /// Presses from off to full brightness
+    #[serde(default = "Light::default_steps")]
+    pub steps: u8,
+    #[serde(default = "Binding::default_role")]
+    pub role: Role,
+}
+
+impl Light {
+    pub fn build(&self) -> ::control::lights::Lights {
+        ::control::lights::Lights::new(self.id, self.brighter, self.dimmer, self.steps)
+    }
+
+    fn default_steps() -> u8 {
+        4
+    }
+}
+
+/// Timed steps run by the motors' gamepad when the button is pressed.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Sequence {
+    pub name: String,
+    pub button: Button,
+    pub steps: Vec<SequenceStep>,
+}
+
+impl Sequence {
+    pub fn build(&self) -> ::control::sequence::Sequence {
+        use control::sequence::Step;
+        use control::thrusters::Wrench;
+        let steps = self.steps
+            .iter()
+            .map(|step| {
+                Step {
+                    duration: step.duration,
+                    demand: Wrench {
+                        surge: step.surge,
+                        sway: step.sway,
+                        heave: step.heave,
+                        yaw: step.yaw,
+                        roll: step.roll,
+                        pitch: step.pitch,
+                    },
+                    commands: step.commands.clone(),
+                }
+            })
+            .collect();
+        ::control::sequence::Sequence::new(self.name.clone(), self.button, steps)
+    }
+}
+
+/// Demands in -1..1 held for `duration` seconds, and commands sent as the step starts.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SequenceStep {
+    pub duration: f64,
+    pub surge: f32,
+    pub sway: f32,
+    pub heave: f32,
+    pub yaw: f32,
+    pub roll: f32,
+    pub pitch: f32,
+    /// Commands other than master power and the motors, which only move through the demands
+    #[serde(deserialize_with = "bindable_list")]
+    pub commands: Vec<RovCommand>,
+}
+
+/// Master power is armed by holding the button for `hold_time` seconds with the sticks centred.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Arming {
+    pub button: Button,
+    pub hold_time: f64,
+}
+
+/// Levels the vehicle while toggled on with the button. The gains act on the tilt in degrees.
+/// Each loop's `max_output` limits how much of the motors' range it may take from the pilot.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Stabiliser {
+    pub button: Button,
+    pub roll: Pid,
+    pub pitch: Pid,
+}
+
+/// The role whose gamepad drives each group of controls.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Roles {
+    pub motors: Role,
+    pub master: Role,
+    pub servos: Role,
 }
 
 use std::default::Default;
 
 impl Default for Servo {
     fn default() -> Servo {
-        Servo { speed: ::control::servo::DEFAULT_MOVE_SPEED }
+        use control::servo::{DEFAULT_MOVE_SPEED, SERVO_LOW, SERVO_HIGH};
+        Servo {
+            speed: DEFAULT_MOVE_SPEED,
+            min: SERVO_LOW,
+            max: SERVO_HIGH,
+            trim: 0,
+            recenter: Button::RightThumb,
+            presets: vec![],
+        }
+    }
+}
+
+impl Default for Motor {
+    fn default() -> Motor {
+        Motor {
+            id: 0,
+            acceleration: 2.0,
+            deceleration: 8.0,
+            reversed: false,
+            forward_scale: 1.0,
+            reverse_scale: 1.0,
+            deadband: 0.0,
+            position: None,
+            direction: None,
+        }
+    }
+}
+
+impl Default for Gain {
+    fn default() -> Gain {
+        let mode = |name: &str, gain: f32| {
+            GainMode {
+                name: name.into(),
+                translation: gain,
+                rotation: gain,
+            }
+        };
+        Gain {
+            button: Button::West,
+            modes: vec![mode("Precision", 0.3), mode("Normal", 0.7), mode("Full", 1.0)],
+            start: 1,
+        }
+    }
+}
+
+impl Default for Controls {
+    fn default() -> Controls {
+        Controls {
+            servo_pan: Servo::default(),
+            servo_tilt: Servo::default(),
+            roles: Roles::default(),
+            shaping: Shaping::default(),
+            motors: vec![],
+            gain: Gain::default(),
+            heading_hold: Hold::heading(),
+            depth_hold: Hold::depth(),
+            stabiliser: Stabiliser::default(),
+            arming: Arming::default(),
+            bindings: Binding::defaults(),
+            manipulator: None,
+            lights: vec![],
+            sequences: vec![],
+            power: None,
+            profiles: vec![],
+            profile_button: Controls::default_profile_button(),
+            trim: Trim::default(),
+            cruise: None,
+        }
+    }
+}
+
+impl Default for Trim {
+    fn default() -> Trim {
+        Trim {
+            offsets: TrimOffsets::default(),
+            step: 0.02,
+            heave_buttons: None,
+            pitch_buttons: None,
+            roll_buttons: None,
+        }
+    }
+}
+
+impl Default for Manipulator {
+    fn default() -> Manipulator {
+        use control::servo::{DEFAULT_MOVE_SPEED, SERVO_LOW, SERVO_HIGH};
+        Manipulator {
+            mode: Mode::Position,
+            jaw: 2,
+            wrist: None,
+            open: SERVO_LOW,
+            closed: SERVO_HIGH,
+            grip: 1.0,
+            speed: DEFAULT_MOVE_SPEED,
+            open_button: Button::LeftTrigger,
+            close_button: Button::RightTrigger,
+            rotate_left: Button::LeftTrigger2,
+            rotate_right: Button::RightTrigger2,
+            role: Role::CoPilot,
+        }
+    }
+}
+
+impl Hold {
+    fn heading() -> Hold {
+        Hold {
+            button: Button::East,
+            pid: Pid {
+                kp: 0.02,
+                ki: 0.002,
+                kd: 0.005,
+                max_output: 0.5,
+            },
+        }
+    }
+
+    fn depth() -> Hold {
+        Hold {
+            button: Button::South,
+            pid: Pid {
+                kp: 1.0,
+                ki: 0.1,
+                kd: 0.2,
+                max_output: 0.6,
+            },
+        }
+    }
+}
+
+impl Default for Arming {
+    fn default() -> Arming {
+        Arming {
+            button: Button::Start,
+            hold_time: 1.0,
+        }
+    }
+}
+
+impl Default for Stabiliser {
+    fn default() -> Stabiliser {
+        let pid = Pid {
+            kp: 0.01,
+            ki: 0.0,
+            kd: 0.002,
+            max_output: 0.3,
+        };
+        Stabiliser {
+            button: Button::LeftThumb,
+            roll: pid.clone(),
+            pitch: pid,
+        }
+    }
+}
+
+impl Default for Depth {
+    fn default() -> Depth {
+        Depth {
+            water: Water::Fresh,
+            surface_pressure: STANDARD_ATMOSPHERE,
+        }
+    }
+}
+
+impl Default for Reconcile {
+    fn default() -> Reconcile {
+        Reconcile { after: 1.0 }
+    }
+}
+
+impl Default for Staleness {
+    fn default() -> Staleness {
+        Staleness {
+            motors: None,
+            servos: None,
+            lights: None,
+            master: Some(1.0),
+            compass: Some(0.5),
+            pressure: Some(1.0),
+        }
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Timing {
+        Timing {
+            control_rate: 200.0,
+            frame_rate: 60.0,
+        }
+    }
+}
+
+impl Default for Bench {
+    fn default() -> Bench {
+        Bench {
+            thrust: 0.15,
+            pulse_time: 1.0,
+        }
+    }
+}
+
+impl Default for Roles {
+    fn default() -> Roles {
+        Roles {
+            motors: Role::Pilot,
+            master: Role::Pilot,
+            servos: Role::CoPilot,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_parse_from_toml() {
+        let config: Controls = ::toml::from_str(r#"
+            [[sequences]]
+            name = "Inspect"
+            button = "Mode"
+
+            [[sequences.steps]]
+            duration = 2.0
+            heave = -0.5
+
+            [[sequences.steps]]
+            commands = [{ command = "ControlServo", id = 0, microseconds = 1200 },
+                        { command = "LightsOn" }]
+        "#)
+            .unwrap();
+        let sequence = &config.sequences[0];
+        assert_eq!(sequence.steps.len(), 2);
+        assert_eq!(sequence.steps[0].heave, -0.5);
+        assert_eq!(sequence.steps[1].duration, 0.0);
+        assert_eq!(sequence.steps[1].commands[1], RovCommand::LightsOn);
+    }
+
+    #[test]
+    fn profile_replaces_only_what_it_sets() {
+        let config: Controls = ::toml::from_str(r#"
+            [[profiles]]
+            name = "Docking"
+            autopilots = ["HeadingHold", "DepthHold"]
+            bindings = []
+
+            [profiles.gain]
+            button = "West"
+            modes = [{ name = "Fine", translation = 0.2, rotation = 0.3 }]
+        "#)
+            .unwrap();
+        let docking = config.with_profile(&config.profiles[0]);
+        assert_eq!(docking.gain.modes[0].name, "Fine");
+        assert!(docking.bindings.is_empty());
+        assert_eq!(docking.shaping.left_stick.deadzone,
+                   config.shaping.left_stick.deadzone);
+        assert_eq!(config.profiles[0].autopilots,
+                   Some(vec![Autopilot::HeadingHold, Autopilot::DepthHold]));
+    }
+
+    #[test]
+    fn profile_trim_replaces_the_offsets_only() {
+        let config: Controls = ::toml::from_str(r#"
+            [trim]
+            heave_buttons = ["LeftThumb", "RightThumb"]
+            offsets = { heave = -0.1 }
+
+            [[profiles]]
+            name = "Survey"
+            trim = { pitch = 0.04 }
+        "#)
+            .unwrap();
+        assert_eq!(config.trim.offsets.heave, -0.1);
+        let survey = config.with_profile(&config.profiles[0]);
+        assert_eq!(survey.trim.offsets,
+                   TrimOffsets {
+                       heave: 0.0,
+                       pitch: 0.04,
+                       roll: 0.0,
+                   });
+        assert_eq!(survey.trim.heave_buttons, Some((Button::LeftThumb, Button::RightThumb)));
+    }
+
+    #[test]
+    fn slew_rates_must_be_above_zero() {
+        let motor = ::toml::from_str::<Controls>("[[motors]]\nid = 1\nacceleration = 0.0\n");
+        assert!(motor.is_err());
+        let profile = ::toml::from_str::<Controls>("[[profiles]]\nname = \"Slow\"\n\
+                                                    deceleration = -1.0\n");
+        assert!(profile.is_err());
+        let profile = ::toml::from_str::<Controls>("[[profiles]]\nname = \"Slow\"\n\
+                                                    deceleration = 0.5\n");
+        assert_eq!(profile.unwrap().profiles[0].deceleration, Some(0.5));
+    }
+
+    #[test]
+    fn bindings_cannot_switch_master_power() {
+        let binding = ::toml::from_str::<Controls>(r#"
+            [[bindings]]
+            button = "Start"
+            action = "Toggle"
+            on = { command = "MasterOn" }
+        "#);
+        assert!(binding.unwrap_err().to_string().contains("arming"));
+        let binding = ::toml::from_str::<Controls>(r#"
+            [[bindings]]
+            button = "North"
+            action = "Momentary"
+            on = { command = "LightsOn" }
+            off = { command = "ControlMotor", id = 0, throttle = 0 }
+        "#);
+        assert!(binding.unwrap_err().to_string().contains("thrusters"));
+    }
+
+    #[test]
+    fn sequence_steps_move_motors_only_through_demand() {
+        let sequence = ::toml::from_str::<Controls>(r#"
+            [[sequences]]
+            name = "Spin"
+            button = "Mode"
+
+            [[sequences.steps]]
+            duration = 1.0
+            commands = [{ command = "LightsOn" },
+                        { command = "ControlMotor", id = 2, throttle = 20000 }]
+        "#);
+        assert!(sequence.unwrap_err().to_string().contains("thrusters"));
+        let sequence = ::toml::from_str::<Controls>(r#"
+            [[sequences]]
+            name = "Look"
+            button = "Mode"
+
+            [[sequences.steps]]
+            yaw = 0.4
+            commands = [{ command = "LightsOn" }]
+        "#);
+        assert_eq!(sequence.unwrap().sequences[0].steps[0].commands, vec![RovCommand::LightsOn]);
+    }
+
+    #[test]
+    fn bindings_parse_from_toml() {
+        let config: Controls = ::toml::from_str(r#"
+            [[bindings]]
+            button = "South"
+            action = "DoubleTap"
+            on = { command = "ControlServo", id = 0, microseconds = 1500 }
+
+            [[bindings]]
+            button = "North"
+            action = "Momentary"
+            on = { command = "LightsOn" }
+            off = { command = "LightsOff" }
+            role = "Pilot"
+        "#)
+            .unwrap();
+        assert_eq!(config.bindings.len(), 2);
+        assert_eq!(config.bindings[0].on,
+                   RovCommand::ControlServo {
+                       id: 0,
+                       microseconds: 1500,
+                   });
+        assert_eq!(config.bindings[0].role, Role::CoPilot);
+        assert_eq!(config.bindings[1].off, Some(RovCommand::LightsOff));
     }
 }
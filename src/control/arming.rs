@@ -0,0 +1,216 @@
+use super::thrusters::Wrench;
+use input::{Button, InputState};
+use rov::RovCommand::{self, MasterOn, MasterOff};
+use rov::RovResponse;
+
+/// How far from zero each part of the demand may be for arming to be allowed.
+const CENTRED: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Disarmed,
+    /// The button is being held; the time it has been held for, in seconds.
+    Arming(f64),
+    Armed,
+}
+
+/// Switches master power. Holding the button long enough with the sticks centred arms the
+/// vehicle, and any press while armed disarms it at once.
+pub struct Arming {
+    // info
+    button: Button,
+    hold_time: f64,
+    // state
+    pub state: State,
+    /// Master power as last reported by the vehicle
+    pub vehicle_on: Option<bool>,
+    /// Why the last attempt to arm was refused
+    pub refused: Option<&'static str>,
+    /// Arming is refused while locked, e.g. until a lost gamepad comes back
+    pub locked: bool,
+    should_send: bool,
+}
+
+impl Arming {
+    pub fn new(button: Button, hold_time: f64) -> Self {
+        Arming {
+            button: button,
+            hold_time: hold_time,
+            state: State::Disarmed,
+            vehicle_on: None,
+            refused: None,
+            locked: false,
+            // The firmware turns master power on when it starts, so turn it off until armed
+            should_send: true,
+        }
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.state == State::Armed
+    }
+
+    pub fn update(&mut self, input: &InputState, demand: &Wrench, delta: f64) {
+        let state = self.state;
+        self.state = match state {
+            State::Armed if input.just_pressed(self.button) => {
+                self.disarm("button pressed");
+                State::Disarmed
+            }
+            State::Armed => State::Armed,
+            State::Disarmed if input.just_pressed(self.button) && self.locked => {
+                warn!("Arming refused; arming is locked");
+                self.refused = Some("Reconnect the gamepad to arm");
+                State::Disarmed
+            }
+            State::Disarmed if input.just_pressed(self.button) => {
+                self.refused = None;
+                State::Arming(0.0)
+            }
+            State::Disarmed => State::Disarmed,
+            State::Arming(_) if !input.is_pressed(self.button) => State::Disarmed,
+            State::Arming(held) if held + delta >= self.hold_time => {
+                if is_centred(demand) {
+                    info!("Armed");
+                    self.should_send = true;
+                    State::Armed
+                } else {
+                    warn!("Arming refused; thrust demand is not centred");
+                    self.refused = Some("Centre the sticks to arm");
+                    State::Disarmed
+                }
+            }
+            State::Arming(held) => State::Arming(held + delta),
+        };
+    }
+
+    /// Drop to disarmed straight away, e.g. when the pilot's gamepad is lost.
+    pub fn disarm(&mut self, reason: &str) {
+        if self.state == State::Armed {
+            info!("Disarmed"; "reason" => reason);
+        }
+        self.state = State::Disarmed;
+        self.should_send = true;
+    }
+
+    /// Follow the master power that the vehicle reports.
+    pub fn observe(&mut self, response: &RovResponse) {
+        match *response {
+            RovResponse::MasterOn => {
+                self.vehicle_on = Some(true);
+                if self.state != State::Armed {
+                    warn!("Vehicle reported master power on while disarmed; turning it off");
+                    self.should_send = true;
+                }
+            }
+            RovResponse::MasterOff => {
+                self.vehicle_on = Some(false);
+                if self.state == State::Armed {
+                    warn!("Vehicle reported master power off; disarming");
+                    self.state = State::Disarmed;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Unlike other controls this clears what it has to send, as `disarm` and `observe` can ask
+    /// for a command outside of `update`.
+    pub fn write_commands(&mut self, output: &mut Vec<RovCommand>) {
+        if self.should_send {
+            output.push(if self.is_armed() { MasterOn } else { MasterOff });
+            self.should_send = false;
+        }
+    }
+
+    pub fn write_status(&self, output: &mut Vec<String>) {
+        match self.state {
+            State::Armed if self.vehicle_on != Some(true) => {
+                output.push("Armed, waiting for vehicle".into())
+            }
+            State::Armed => {}
+            State::Arming(held) => {
+                output.push(format!("Arming in {:.1} s", (self.hold_time - held).max(0.0)))
+            }
+            State::Disarmed => {
+                output.push(self.refused.unwrap_or("Disarmed").into());
+            }
+        }
+    }
+}
+
+fn is_centred(demand: &Wrench) -> bool {
+    [demand.surge, demand.sway, demand.heave, demand.yaw, demand.roll, demand.pitch]
+        .iter()
+        .all(|part| part.abs() < CENTRED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hold_button(arming: &mut Arming, demand: &Wrench, seconds: f64) {
+        let mut input = InputState::new();
+        let mut held = 0.0;
+        while held < seconds {
+            input.advance();
+            input.press(Button::Start);
+            arming.update(&input, demand, 0.1);
+            held += 0.1;
+        }
+        input.advance();
+        arming.update(&input, demand, 0.1);
+    }
+
+    #[test]
+    fn long_press_arms_and_any_press_disarms() {
+        let mut arming = Arming::new(Button::Start, 1.0);
+        let mut commands = vec![];
+        arming.write_commands(&mut commands);
+        assert_eq!(commands, vec![MasterOff]);
+
+        hold_button(&mut arming, &Wrench::default(), 0.5);
+        assert_eq!(arming.state, State::Disarmed);
+
+        hold_button(&mut arming, &Wrench::default(), 1.5);
+        assert!(arming.is_armed());
+
+        let mut input = InputState::new();
+        input.press(Button::Start);
+        arming.update(&input, &Wrench::default(), 0.1);
+        assert_eq!(arming.state, State::Disarmed);
+        let mut commands = vec![];
+        arming.write_commands(&mut commands);
+        assert_eq!(commands, vec![MasterOff]);
+    }
+
+    #[test]
+    fn arming_is_refused_with_demand() {
+        let mut arming = Arming::new(Button::Start, 1.0);
+        hold_button(&mut arming, &Wrench { surge: 0.5, ..Wrench::default() }, 1.5);
+        assert_eq!(arming.state, State::Disarmed);
+        assert!(arming.refused.is_some());
+    }
+
+    #[test]
+    fn locked_arming_is_refused() {
+        let mut arming = Arming::new(Button::Start, 1.0);
+        arming.locked = true;
+        hold_button(&mut arming, &Wrench::default(), 1.5);
+        assert_eq!(arming.state, State::Disarmed);
+
+        arming.locked = false;
+        hold_button(&mut arming, &Wrench::default(), 1.5);
+        assert!(arming.is_armed());
+    }
+
+    #[test]
+    fn vehicle_turning_off_disarms() {
+        let mut arming = Arming::new(Button::Start, 1.0);
+        hold_button(&mut arming, &Wrench::default(), 1.5);
+        arming.observe(&RovResponse::MasterOn);
+        assert!(arming.is_armed());
+
+        arming.observe(&RovResponse::MasterOff);
+        assert_eq!(arming.state, State::Disarmed);
+    }
+}
@@ -0,0 +1,77 @@
+use super::pid::Pid;
+use input::{Button, InputState};
+
+/// Levels the vehicle by asking for roll and pitch moments against its tilt.
+///
+/// The moments are limited by each loop's output limit. They are added to the pilot's demand and
+/// each motor clamps the sum, so near full throttle some of the levelling is lost.
+pub struct Stabiliser {
+    // info
+    button: Button,
+    pub roll: Pid,
+    pub pitch: Pid,
+    // state
+    pub enabled: bool,
+    /// (roll, pitch) in degrees
+    attitude: Option<(f32, f32)>,
+}
+
+impl Stabiliser {
+    pub fn new(button: Button, roll: Pid, pitch: Pid) -> Self {
+        Stabiliser {
+            button: button,
+            roll: roll,
+            pitch: pitch,
+            enabled: false,
+            attitude: None,
+        }
+    }
+
+    /// Give the loops the latest roll and pitch, or `None` when the IMU isn't available.
+    pub fn observe(&mut self, attitude: Option<(f32, f32)>) {
+        self.attitude = attitude;
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled != self.enabled {
+            self.enabled = enabled;
+            self.roll.reset();
+            self.pitch.reset();
+        }
+    }
+
+    /// The roll and pitch moments to add to the demand.
+    pub fn update(&mut self, input: &InputState, delta: f64) -> (f32, f32) {
+        if input.just_pressed(self.button) {
+            let enabled = !self.enabled;
+            self.set_enabled(enabled);
+        }
+
+        match self.attitude {
+            Some((roll, pitch)) if self.enabled => {
+                (self.roll.update(0.0, roll, delta as f32),
+                 self.pitch.update(0.0, pitch, delta as f32))
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_is_countered_within_the_limit() {
+        let pid = || Pid::new(0.1, 0.0, 0.0, 0.3);
+        let mut stabiliser = Stabiliser::new(Button::LeftThumb, pid(), pid());
+        let mut input = InputState::new();
+        stabiliser.observe(Some((-2.0, 10.0)));
+        assert_eq!(stabiliser.update(&input, 0.005), (0.0, 0.0));
+
+        input.press(Button::LeftThumb);
+        let (roll, pitch) = stabiliser.update(&input, 0.005);
+        assert!((roll - 0.2).abs() < 1e-6);
+        assert_eq!(pitch, -0.3);
+    }
+}
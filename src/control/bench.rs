@@ -0,0 +1,126 @@
+//! Timed thrust pulses for checking motors one at a time on the bench.
+
+use std::collections::VecDeque;
+
+use rov::RovCommand;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pulse {
+    pub id: u8,
+    /// In -1..1; zero pauses between pulses
+    pub thrust: f32,
+    /// In seconds
+    pub duration: f64,
+}
+
+/// Runs pulses one after another. Each motor is stopped when its pulse ends.
+pub struct Pulses {
+    queue: VecDeque<Pulse>,
+    /// The pulse being run and how long it has run for
+    current: Option<(Pulse, f64)>,
+}
+
+impl Pulses {
+    pub fn new() -> Pulses {
+        Pulses {
+            queue: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    pub fn current(&self) -> Option<Pulse> {
+        self.current.map(|(pulse, _)| pulse)
+    }
+
+    /// How many pulses are left, counting the one being run.
+    pub fn remaining(&self) -> usize {
+        self.queue.len() + if self.current.is_some() { 1 } else { 0 }
+    }
+
+    pub fn push(&mut self, pulse: Pulse) {
+        self.queue.push_back(pulse);
+    }
+
+    /// Queue a forward and a reverse pulse on each motor in turn, with pauses in between.
+    pub fn sequence(&mut self, ids: &[u8], thrust: f32, duration: f64) {
+        for &id in ids.iter() {
+            for &thrust in [thrust, 0.0, -thrust, 0.0].iter() {
+                self.push(Pulse {
+                    id: id,
+                    thrust: thrust,
+                    duration: duration,
+                });
+            }
+        }
+    }
+
+    /// Drop every queued pulse and stop the motor that is running.
+    pub fn abort(&mut self, output: &mut Vec<RovCommand>) {
+        self.queue.clear();
+        if let Some((pulse, _)) = self.current.take() {
+            output.push(throttle(pulse.id, 0.0));
+        }
+    }
+
+    pub fn update(&mut self, delta: f64, output: &mut Vec<RovCommand>) {
+        if let Some((pulse, elapsed)) = self.current.take() {
+            let elapsed = elapsed + delta;
+            if elapsed < pulse.duration {
+                self.current = Some((pulse, elapsed));
+                return;
+            }
+            output.push(throttle(pulse.id, 0.0));
+        }
+        if let Some(pulse) = self.queue.pop_front() {
+            output.push(throttle(pulse.id, pulse.thrust));
+            self.current = Some((pulse, 0.0));
+        }
+    }
+}
+
+fn throttle(id: u8, thrust: f32) -> RovCommand {
+    RovCommand::ControlMotor {
+        id: id,
+        throttle: (thrust * super::INT_MAX) as i16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_runs_each_motor_in_turn() {
+        let mut pulses = Pulses::new();
+        pulses.sequence(&[0, 1], 0.5, 1.0);
+        assert_eq!(pulses.remaining(), 8);
+
+        let mut commands = vec![];
+        pulses.update(0.1, &mut commands);
+        assert_eq!(commands, vec![throttle(0, 0.5)]);
+
+        let mut commands = vec![];
+        pulses.update(0.5, &mut commands);
+        assert!(commands.is_empty());
+
+        // The pulse ends, and the pause after it begins
+        pulses.update(0.5, &mut commands);
+        assert_eq!(commands, vec![throttle(0, 0.0), throttle(0, 0.0)]);
+        for _ in 0..6 {
+            pulses.update(1.0, &mut vec![]);
+        }
+        assert_eq!(pulses.current().map(|pulse| pulse.id), Some(1));
+    }
+
+    #[test]
+    fn abort_stops_the_running_motor() {
+        let mut pulses = Pulses::new();
+        pulses.sequence(&[2], 0.5, 1.0);
+        pulses.update(0.1, &mut vec![]);
+
+        let mut commands = vec![];
+        pulses.abort(&mut commands);
+        assert_eq!(commands, vec![throttle(2, 0.0)]);
+        assert_eq!(pulses.remaining(), 0);
+    }
+}
@@ -0,0 +1,225 @@
+use super::Control;
+use input::{Button, InputState};
+use rov::RovCommand;
+
+/// What has to be done with a button to trigger a binding.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Each press switches between the on and off commands.
+    Toggle,
+    /// The on command is sent when the button goes down and the off command when it comes up.
+    Momentary,
+    /// The button has to be held down for a while.
+    LongPress,
+    /// The button has to be pressed twice in quick succession.
+    DoubleTap,
+}
+
+/// Sends commands when a button is used in a certain way. Long presses and double taps switch
+/// between the on and off commands like a toggle when there is an off command, and otherwise send
+/// the on command every time.
+pub struct Binding {
+    // info
+    button: Button,
+    action: Action,
+    on: RovCommand,
+    off: Option<RovCommand>,
+    hold_time: f64,
+    double_tap_time: f64,
+    // state
+    pub active: bool,
+    /// How long the button has been held for
+    held: f64,
+    /// Whether the current long press has already fired
+    fired: bool,
+    /// Time since the first tap of a possible double tap
+    since_tap: Option<f64>,
+    pending: Vec<RovCommand>,
+}
+
+impl Binding {
+    pub fn new(button: Button, action: Action, on: RovCommand, off: Option<RovCommand>) -> Self {
+        Binding {
+            button: button,
+            action: action,
+            on: on,
+            off: off,
+            hold_time: 1.0,
+            double_tap_time: 0.3,
+            active: false,
+            held: 0.0,
+            fired: false,
+            since_tap: None,
+            pending: vec![],
+        }
+    }
+
+    /// How long a long press lasts and how close together a double tap's presses must be, in
+    /// seconds.
+    pub fn timing(mut self, hold_time: f64, double_tap_time: f64) -> Self {
+        self.hold_time = hold_time;
+        self.double_tap_time = double_tap_time;
+        self
+    }
+
+    fn trigger(&mut self) {
+        match self.off.clone() {
+            Some(off) => {
+                self.active = !self.active;
+                self.pending.push(if self.active { self.on.clone() } else { off });
+            }
+            None => self.pending.push(self.on.clone()),
+        }
+    }
+}
+
+impl Control for Binding {
+    fn update(&mut self, input: &InputState, delta: f64) {
+        self.pending.clear();
+        let pressed = input.just_pressed(self.button);
+        let released = input.just_released(self.button);
+
+        match self.action {
+            Action::Toggle => {
+                if pressed {
+                    self.trigger();
+                }
+            }
+            Action::Momentary => {
+                if pressed {
+                    self.active = true;
+                    self.pending.push(self.on.clone());
+                } else if released {
+                    self.active = false;
+                    if let Some(off) = self.off.clone() {
+                        self.pending.push(off);
+                    }
+                }
+            }
+            Action::LongPress => {
+                if input.is_pressed(self.button) {
+                    self.held += delta;
+                    if self.held >= self.hold_time && !self.fired {
+                        self.fired = true;
+                        self.trigger();
+                    }
+                } else {
+                    self.held = 0.0;
+                    self.fired = false;
+                }
+            }
+            Action::DoubleTap => {
+                let double_tap_time = self.double_tap_time;
+                self.since_tap = self.since_tap
+                    .map(|since| since + delta)
+                    .and_then(|since| if since <= double_tap_time { Some(since) } else { None });
+                if pressed {
+                    if self.since_tap.is_some() {
+                        self.since_tap = None;
+                        self.trigger();
+                    } else {
+                        self.since_tap = Some(0.0);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_commands(&self, output: &mut Vec<RovCommand>) {
+        output.extend(self.pending.iter().cloned());
+    }
+
+    fn release(&mut self, output: &mut Vec<RovCommand>) {
+        if self.active {
+            self.active = false;
+            if let Some(off) = self.off.clone() {
+                output.push(off);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rov::RovCommand::{LightsOn, LightsOff};
+
+    fn step(binding: &mut Binding,
+            input: &mut InputState,
+            press: bool,
+            delta: f64)
+            -> Vec<RovCommand> {
+        input.advance();
+        if press {
+            input.press(Button::North);
+        }
+        binding.update(input, delta);
+        let mut commands = vec![];
+        binding.write_commands(&mut commands);
+        commands
+    }
+
+    #[test]
+    fn toggle_switches_once_per_press() {
+        let mut binding = Binding::new(Button::North, Action::Toggle, LightsOn, Some(LightsOff));
+        let mut input = InputState::new();
+        assert_eq!(step(&mut binding, &mut input, true, 0.1), vec![LightsOn]);
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        assert!(step(&mut binding, &mut input, false, 0.1).is_empty());
+        assert_eq!(step(&mut binding, &mut input, true, 0.1), vec![LightsOff]);
+    }
+
+    #[test]
+    fn momentary_follows_the_button() {
+        let mut binding = Binding::new(Button::North, Action::Momentary, LightsOn, Some(LightsOff));
+        let mut input = InputState::new();
+        assert_eq!(step(&mut binding, &mut input, true, 0.1), vec![LightsOn]);
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        assert_eq!(step(&mut binding, &mut input, false, 0.1), vec![LightsOff]);
+    }
+
+    #[test]
+    fn long_press_fires_once_after_the_hold_time() {
+        let mut binding = Binding::new(Button::North, Action::LongPress, LightsOn, None)
+            .timing(0.25, 0.3);
+        let mut input = InputState::new();
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        assert_eq!(step(&mut binding, &mut input, true, 0.1), vec![LightsOn]);
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+
+        // A short press does nothing
+        step(&mut binding, &mut input, false, 0.1);
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        assert!(step(&mut binding, &mut input, false, 0.1).is_empty());
+    }
+
+    #[test]
+    fn double_tap_needs_presses_close_together() {
+        let mut binding = Binding::new(Button::North, Action::DoubleTap, LightsOn, None)
+            .timing(1.0, 0.3);
+        let mut input = InputState::new();
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+        step(&mut binding, &mut input, false, 0.1);
+        assert_eq!(step(&mut binding, &mut input, true, 0.1), vec![LightsOn]);
+
+        step(&mut binding, &mut input, false, 0.1);
+        step(&mut binding, &mut input, true, 0.1);
+        step(&mut binding, &mut input, false, 0.5);
+        assert!(step(&mut binding, &mut input, true, 0.1).is_empty());
+    }
+
+    #[test]
+    fn releasing_switches_off_what_is_on() {
+        let mut binding = Binding::new(Button::North, Action::Toggle, LightsOn, Some(LightsOff));
+        let mut input = InputState::new();
+        step(&mut binding, &mut input, true, 0.1);
+        let mut commands = vec![];
+        binding.release(&mut commands);
+        assert_eq!(commands, vec![LightsOff]);
+
+        commands.clear();
+        binding.release(&mut commands);
+        assert!(commands.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use input::{Button, InputState};
+
+/// How far the stick must be pushed, either to latch or to cancel.
+const STICK_MOVED: f32 = 0.1;
+
+/// Keeps the forward demand the stick had when the button was pressed, so the pilot can let go
+/// of it on a long run. Pushing further the same way goes faster for as long as it's held;
+/// pulling the other way, or pressing the button again, lets go.
+pub struct Cruise {
+    // info
+    button: Button,
+    // state
+    /// The latched surge in -1..1
+    pub surge: Option<f32>,
+}
+
+impl Cruise {
+    pub fn new(button: Button) -> Self {
+        Cruise {
+            button: button,
+            surge: None,
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        if self.surge.take().is_some() {
+            info!("Cruise cancelled");
+        }
+    }
+
+    /// The surge to use in place of the stick's.
+    pub fn update(&mut self, input: &InputState, surge: f32) -> f32 {
+        match self.surge {
+            Some(_) if input.just_pressed(self.button) => self.cancel(),
+            Some(latched) if surge * latched < 0.0 && surge.abs() > STICK_MOVED => self.cancel(),
+            None if input.just_pressed(self.button) && surge.abs() > STICK_MOVED => {
+                info!("Cruise engaged"; "surge" => surge);
+                self.surge = Some(surge);
+            }
+            _ => {}
+        }
+        match self.surge {
+            Some(latched) if latched.abs() > surge.abs() => latched,
+            _ => surge,
+        }
+    }
+
+    pub fn write_status(&self, output: &mut Vec<String>) {
+        if let Some(surge) = self.surge {
+            output.push(format!("Cruising at {:+.0}%", surge * 100.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latched_surge_holds_until_stick_is_pulled_back() {
+        let mut cruise = Cruise::new(Button::RightThumb);
+        let mut input = InputState::new();
+        input.press(Button::RightThumb);
+        assert_eq!(cruise.update(&input, 0.6), 0.6);
+        assert_eq!(cruise.surge, Some(0.6));
+
+        input.advance();
+        assert_eq!(cruise.update(&input, 0.0), 0.6);
+        assert_eq!(cruise.update(&input, 0.9), 0.9);
+        assert_eq!(cruise.update(&input, -0.05), 0.6);
+        assert_eq!(cruise.update(&input, -0.3), -0.3);
+        assert_eq!(cruise.surge, None);
+        assert_eq!(cruise.update(&input, 0.0), 0.0);
+    }
+
+    #[test]
+    fn button_does_not_latch_a_centred_stick() {
+        let mut cruise = Cruise::new(Button::RightThumb);
+        let mut input = InputState::new();
+        input.press(Button::RightThumb);
+        cruise.update(&input, 0.05);
+        assert_eq!(cruise.surge, None);
+    }
+}
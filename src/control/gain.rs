@@ -0,0 +1,40 @@
+use super::thrusters::Wrench;
+use config::GainMode;
+use input::{Button, InputState};
+
+/// Cycles through profiles that scale the pilot's demand.
+pub struct Gain {
+    // info
+    button: Button,
+    modes: Vec<GainMode>,
+    // state
+    index: usize,
+}
+
+impl Gain {
+    pub fn new(button: Button, modes: Vec<GainMode>, start: usize) -> Self {
+        let index = if start < modes.len() { start } else { 0 };
+        Gain {
+            button: button,
+            modes: modes,
+            index: index,
+        }
+    }
+
+    pub fn mode(&self) -> Option<&GainMode> {
+        self.modes.get(self.index)
+    }
+
+    pub fn update(&mut self, input: &InputState) {
+        if input.just_pressed(self.button) && !self.modes.is_empty() {
+            self.index = (self.index + 1) % self.modes.len();
+        }
+    }
+
+    pub fn apply(&self, demand: &Wrench) -> Wrench {
+        match self.mode() {
+            Some(mode) => demand.scaled(mode.translation, mode.rotation),
+            None => *demand,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use super::pid::Pid;
+use input::{Button, InputState};
+
+/// How far the pilot's demand may be from zero while still counting as centred.
+const CENTRED: f32 = 0.05;
+
+/// Holds a measurement, like the heading or depth, at the value it had when the pilot let go of
+/// the stick that controls it.
+pub struct Hold {
+    // info
+    button: Button,
+    pub pid: Pid,
+    /// Error for the loop given the target and the measurement
+    error: fn(f32, f32) -> f32,
+    // state
+    pub enabled: bool,
+    pub target: Option<f32>,
+    pub measurement: Option<f32>,
+    /// The demand added on the last update
+    pub output: f32,
+}
+
+impl Hold {
+    pub fn new(button: Button, pid: Pid, error: fn(f32, f32) -> f32) -> Self {
+        Hold {
+            button: button,
+            pid: pid,
+            error: error,
+            enabled: false,
+            target: None,
+            measurement: None,
+            output: 0.0,
+        }
+    }
+
+    /// Give the loop the latest measurement, or `None` when the sensor isn't available.
+    pub fn observe(&mut self, measurement: Option<f32>) {
+        self.measurement = measurement;
+    }
+
+    /// The demand to add to the pilot's.
+    pub fn update(&mut self, input: &InputState, pilot_demand: f32, delta: f64) -> f32 {
+        if input.just_pressed(self.button) {
+            self.enabled = !self.enabled;
+        }
+
+        let measurement = match self.measurement {
+            Some(measurement) if self.enabled && pilot_demand.abs() < CENTRED => measurement,
+            _ => {
+                self.target = None;
+                self.output = 0.0;
+                return 0.0;
+            }
+        };
+
+        let target = match self.target {
+            Some(target) => target,
+            None => {
+                self.pid.reset();
+                self.target = Some(measurement);
+                measurement
+            }
+        };
+
+        // The loop drives the error to zero, which keeps the heading's wrap-around out of the PID
+        let error = (self.error)(target, measurement);
+        self.output = self.pid.update(0.0, -error, delta as f32);
+        self.output
+    }
+}
+
+/// The shortest turn from the heading to the target, in degrees between -180 and 180.
+pub fn heading_error(target: f32, heading: f32) -> f32 {
+    let difference = (target - heading) % 360.0;
+    if difference > 180.0 {
+        difference - 360.0
+    } else if difference < -180.0 {
+        difference + 360.0
+    } else {
+        difference
+    }
+}
+
+/// How far the vehicle is below the target depth, so that a positive error asks for ascent.
+pub fn depth_error(target: f32, depth: f32) -> f32 {
+    depth - target
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_error_takes_the_short_way_round() {
+        assert_eq!(heading_error(10.0, 350.0), 20.0);
+        assert_eq!(heading_error(350.0, 10.0), -20.0);
+    }
+
+    #[test]
+    fn holds_value_captured_when_stick_centred() {
+        let mut hold = Hold::new(Button::East, Pid::new(0.02, 0.0, 0.0, 0.5), heading_error);
+        let mut input = InputState::new();
+        input.press(Button::East);
+
+        hold.observe(Some(90.0));
+        assert_eq!(hold.update(&input, 0.0, 0.005), 0.0);
+        assert_eq!(hold.target, Some(90.0));
+
+        // Drifting left turns back right
+        input.advance();
+        hold.observe(Some(80.0));
+        assert!((hold.update(&input, 0.0, 0.005) - 0.2).abs() < 1e-6);
+
+        // The pilot turning releases the heading
+        assert_eq!(hold.update(&input, 0.5, 0.005), 0.0);
+        assert_eq!(hold.target, None);
+    }
+
+    #[test]
+    fn sinking_below_held_depth_asks_for_ascent() {
+        let mut hold = Hold::new(Button::South, Pid::new(0.5, 0.0, 0.0, 0.5), depth_error);
+        let mut input = InputState::new();
+        input.press(Button::South);
+
+        hold.observe(Some(2.0));
+        hold.update(&input, 0.0, 0.005);
+        input.advance();
+        hold.observe(Some(2.4));
+        assert!((hold.update(&input, 0.0, 0.005) - 0.2).abs() < 1e-6);
+    }
+}
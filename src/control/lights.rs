@@ -1,50 +1,115 @@
-
 use super::Control;
-use gilrs;
-use rov::RovCommand::{self, LightsOn, LightsOff};
+use input::{Button, InputState};
+use rov::{RovCommand, RovResponse};
 
+/// A dimmable light channel, made brighter or dimmer a step at a time.
 pub struct Lights {
     // info
-    button: gilrs::Button,
+    pub id: u8,
+    brighter_button: Button,
+    dimmer_button: Button,
+    step: u8,
     // state
-    lights_state: bool,
-    was_pressed: bool,
-    need_to_write: bool,
+    pub level: u8,
+    prev_level: u8,
 }
 
 impl Lights {
-    pub fn new(button: gilrs::Button) -> Self {
+    /// Full brightness is `steps` presses away from off.
+    pub fn new(id: u8, brighter_button: Button, dimmer_button: Button, steps: u8) -> Self {
+        let steps = steps.max(1);
         Lights {
-            button: button,
-            lights_state: false,
-            was_pressed: false,
-            need_to_write: true,
+            id: id,
+            brighter_button: brighter_button,
+            dimmer_button: dimmer_button,
+            // Rounded up, so that the last step reaches full brightness
+            step: ((255 + steps as u16 - 1) / steps as u16) as u8,
+            level: 0,
+            prev_level: 0,
         }
     }
 }
 
 impl Control for Lights {
-    fn update(&mut self, input: &gilrs::GamepadState, _delta: f64) {
-        let is_pressed = input.is_pressed(self.button);
-        match (self.was_pressed, is_pressed) {
-            (false, true) => {
-                self.lights_state = !self.lights_state;
-                self.need_to_write = true;
-            }
-            _ => {
-                self.need_to_write = false;
-            }
+    fn update(&mut self, input: &InputState, _delta: f64) {
+        self.prev_level = self.level;
+        if input.just_pressed(self.brighter_button) {
+            self.level = self.level.saturating_add(self.step);
+        }
+        if input.just_pressed(self.dimmer_button) {
+            self.level = self.level.saturating_sub(self.step);
         }
-        self.was_pressed = is_pressed;
     }
 
     fn write_commands(&self, output: &mut Vec<RovCommand>) {
-        if self.need_to_write {
-            output.push(if self.lights_state {
-                LightsOn
-            } else {
-                LightsOff
+        if self.level != self.prev_level {
+            output.push(RovCommand::SetLight {
+                id: self.id,
+                level: self.level,
             });
         }
     }
+
+    /// The ROV turns the lights off whenever master power is switched.
+    fn observe(&mut self, response: &RovResponse) {
+        match *response {
+            RovResponse::MasterOn | RovResponse::MasterOff => {
+                self.level = 0;
+                self.prev_level = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_reach_full_brightness_and_off() {
+        let mut lights = Lights::new(1, Button::North, Button::South, 3);
+        let mut input = InputState::new();
+        for &level in [85, 170, 255, 255].iter() {
+            input.advance();
+            lights.update(&input, 0.1);
+            input.advance();
+            input.press(Button::North);
+            lights.update(&input, 0.1);
+            assert_eq!(lights.level, level);
+        }
+
+        let mut commands = vec![];
+        lights.write_commands(&mut commands);
+        assert!(commands.is_empty());
+
+        input.advance();
+        lights.update(&input, 0.1);
+        input.advance();
+        input.press(Button::South);
+        lights.update(&input, 0.1);
+        lights.write_commands(&mut commands);
+        assert_eq!(commands, vec![RovCommand::SetLight { id: 1, level: 170 }]);
+    }
+
+    #[test]
+    fn master_power_switching_turns_the_level_off() {
+        let mut lights = Lights::new(0, Button::North, Button::South, 3);
+        let mut input = InputState::new();
+        input.press(Button::North);
+        lights.update(&input, 0.1);
+        assert_eq!(lights.level, 85);
+
+        lights.observe(&RovResponse::MasterOff);
+        assert_eq!(lights.level, 0);
+        let mut commands = vec![];
+        lights.write_commands(&mut commands);
+        assert!(commands.is_empty());
+
+        input.advance();
+        input.advance();
+        input.press(Button::North);
+        lights.update(&input, 0.1);
+        assert_eq!(lights.level, 85);
+    }
 }
@@ -0,0 +1,278 @@
+use super::Control;
+use super::servo::{SERVO_LOW, SERVO_MID, SERVO_HIGH};
+use input::{Button, InputState};
+use rov::{RovCommand, RovResponse};
+
+/// How the manipulator's servo channels are driven.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Pulse widths are positions, as for hobby servos. The jaw and wrist move while a button is
+    /// held and stay put when it is let go.
+    Position,
+    /// Pulse widths are speeds with the middle stopped, as for PWM motor grippers. The jaw and
+    /// wrist only move while a button is held.
+    Velocity,
+}
+
+/// A claw on one servo channel, with an optional wrist on another.
+///
+/// `open` and `closed` are the jaw's pulse widths at either end of its travel in position mode,
+/// or at full speed each way in velocity mode. The grip limit is the share of the closing travel,
+/// or of the closing drive, that may be used, which bounds how hard the jaw squeezes.
+pub struct Manipulator {
+    // info
+    mode: Mode,
+    jaw_id: u8,
+    open: i16,
+    closed: i16,
+    grip: f32,
+    /// How fast the jaw and wrist move in position mode, in microseconds per second
+    speed: f64,
+    open_button: Button,
+    close_button: Button,
+    wrist: Option<(u8, Button, Button)>,
+    // state
+    /// Unrounded positions, so that small steps at the control rate add up
+    jaw_position: f64,
+    wrist_position: f64,
+    pub jaw: i16,
+    pub wrist_microseconds: i16,
+    prev_jaw: i16,
+    prev_wrist: i16,
+    /// Whether the ROV has put both channels back to `SERVO_MID` since the last update
+    reset: bool,
+}
+
+impl Manipulator {
+    pub fn new(mode: Mode, jaw_id: u8, open: i16, closed: i16, speed: f64) -> Self {
+        let jaw = match mode {
+            Mode::Position => open,
+            Mode::Velocity => SERVO_MID,
+        };
+        Manipulator {
+            mode: mode,
+            jaw_id: jaw_id,
+            open: open,
+            closed: closed,
+            grip: 1.0,
+            speed: speed,
+            open_button: Button::LeftTrigger,
+            close_button: Button::RightTrigger,
+            wrist: None,
+            jaw_position: jaw as f64,
+            wrist_position: SERVO_MID as f64,
+            jaw: jaw,
+            wrist_microseconds: SERVO_MID,
+            prev_jaw: jaw,
+            prev_wrist: SERVO_MID,
+            reset: false,
+        }
+    }
+
+    pub fn buttons(mut self, open: Button, close: Button) -> Self {
+        self.open_button = open;
+        self.close_button = close;
+        self
+    }
+
+    /// Turn a wrist on another channel with a pair of buttons.
+    pub fn wrist(mut self, id: u8, left: Button, right: Button) -> Self {
+        self.wrist = Some((id, left, right));
+        self
+    }
+
+    /// In 0..1
+    pub fn grip(mut self, grip: f32) -> Self {
+        self.grip = grip.max(0.0).min(1.0);
+        self
+    }
+
+    /// The furthest the jaw may close, or its drive while closing.
+    pub fn grip_limit(&self) -> i16 {
+        let from = match self.mode {
+            Mode::Position => self.open,
+            Mode::Velocity => SERVO_MID,
+        };
+        from + ((self.closed - from) as f32 * self.grip) as i16
+    }
+
+    /// Whether the jaw is squeezing as hard as it is allowed to.
+    fn at_grip_limit(&self) -> bool {
+        self.jaw == self.grip_limit() && (self.mode == Mode::Position || self.jaw != SERVO_MID)
+    }
+}
+
+/// Move towards a position by no more than a step.
+fn approach(from: f64, to: i16, step: f64) -> f64 {
+    let to = to as f64;
+    if (to - from).abs() <= step {
+        to
+    } else if to > from {
+        from + step
+    } else {
+        from - step
+    }
+}
+
+/// -1, 0 or 1 for a pair of opposing buttons.
+fn direction(input: &InputState, negative: Button, positive: Button) -> i16 {
+    match (input.is_pressed(negative), input.is_pressed(positive)) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => 0,
+    }
+}
+
+impl Control for Manipulator {
+    fn update(&mut self, input: &InputState, delta: f64) {
+        // Once reset, the jaw and wrist are sent where they should be even if they haven't moved
+        if self.reset {
+            self.prev_jaw = SERVO_MID;
+            self.prev_wrist = SERVO_MID;
+            self.reset = false;
+        } else {
+            self.prev_jaw = self.jaw;
+            self.prev_wrist = self.wrist_microseconds;
+        }
+        let jaw = direction(input, self.open_button, self.close_button);
+        let wrist = self.wrist.map_or(0, |(_, left, right)| direction(input, left, right));
+
+        match self.mode {
+            Mode::Position => {
+                let step = self.speed * delta;
+                let limit = self.grip_limit();
+                self.jaw_position = match jaw {
+                    1 => approach(self.jaw_position, limit, step),
+                    -1 => approach(self.jaw_position, self.open, step),
+                    _ => self.jaw_position,
+                };
+                self.wrist_position = (self.wrist_position + wrist as f64 * step)
+                    .max(SERVO_LOW as f64)
+                    .min(SERVO_HIGH as f64);
+                self.jaw = self.jaw_position.round() as i16;
+                self.wrist_microseconds = self.wrist_position.round() as i16;
+            }
+            Mode::Velocity => {
+                self.jaw = match jaw {
+                    1 => self.grip_limit(),
+                    -1 => self.open,
+                    _ => SERVO_MID,
+                };
+                // The wrist turns as fast as the jaw opens
+                self.wrist_microseconds = SERVO_MID + wrist * (self.open - SERVO_MID).abs();
+                self.jaw_position = self.jaw as f64;
+                self.wrist_position = self.wrist_microseconds as f64;
+            }
+        }
+    }
+
+    fn write_commands(&self, output: &mut Vec<RovCommand>) {
+        if self.jaw != self.prev_jaw {
+            output.push(RovCommand::ControlServo {
+                id: self.jaw_id,
+                microseconds: self.jaw,
+            });
+        }
+        if let Some((id, _, _)) = self.wrist {
+            if self.wrist_microseconds != self.prev_wrist {
+                output.push(RovCommand::ControlServo {
+                    id: id,
+                    microseconds: self.wrist_microseconds,
+                });
+            }
+        }
+    }
+
+    fn write_status(&self, output: &mut Vec<String>) {
+        if self.at_grip_limit() && self.grip < 1.0 {
+            output.push(format!("Gripper limited to {:.0}% grip", self.grip * 100.0));
+        }
+    }
+
+    /// The ROV puts every servo channel to `SERVO_MID` whenever master power is switched.
+    fn observe(&mut self, response: &RovResponse) {
+        match *response {
+            RovResponse::MasterOn | RovResponse::MasterOff => self.reset = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(manipulator: &mut Manipulator, buttons: &[Button], delta: f64) -> Vec<RovCommand> {
+        let mut input = InputState::new();
+        for &button in buttons.iter() {
+            input.press(button);
+        }
+        manipulator.update(&input, delta);
+        let mut commands = vec![];
+        manipulator.write_commands(&mut commands);
+        commands
+    }
+
+    #[test]
+    fn position_jaw_closes_no_further_than_the_grip_limit() {
+        let mut claw = Manipulator::new(Mode::Position, 2, 1000, 2000, 500.0).grip(0.8);
+        step(&mut claw, &[Button::RightTrigger], 1.0);
+        assert_eq!(claw.jaw, 1500);
+        step(&mut claw, &[Button::RightTrigger], 1.0);
+        assert_eq!(claw.jaw, 1800);
+
+        // Letting go keeps the jaw where it is
+        assert!(step(&mut claw, &[], 1.0).is_empty());
+        assert_eq!(claw.jaw, 1800);
+        step(&mut claw, &[Button::LeftTrigger], 10.0);
+        assert_eq!(claw.jaw, 1000);
+    }
+
+    #[test]
+    fn position_jaw_and_wrist_move_at_speed_at_the_control_rate() {
+        let mut claw = Manipulator::new(Mode::Position, 2, 1000, 2000, 150.0)
+            .wrist(3, Button::LeftTrigger2, Button::RightTrigger2);
+        for _ in 0..200 {
+            step(&mut claw, &[Button::RightTrigger, Button::RightTrigger2], 0.005);
+        }
+        assert_eq!((claw.jaw, claw.wrist_microseconds), (1150, SERVO_MID + 150));
+    }
+
+    #[test]
+    fn velocity_jaw_drives_only_while_held() {
+        let mut claw = Manipulator::new(Mode::Velocity, 2, 1000, 2000, 500.0)
+            .grip(0.5)
+            .wrist(3, Button::LeftTrigger2, Button::RightTrigger2);
+        let commands = step(&mut claw, &[Button::RightTrigger, Button::LeftTrigger2], 0.1);
+        assert_eq!(commands,
+                   vec![RovCommand::ControlServo {
+                            id: 2,
+                            microseconds: 1750,
+                        },
+                        RovCommand::ControlServo {
+                            id: 3,
+                            microseconds: 1000,
+                        }]);
+
+        step(&mut claw, &[], 0.1);
+        assert_eq!((claw.jaw, claw.wrist_microseconds), (SERVO_MID, SERVO_MID));
+    }
+
+    #[test]
+    fn position_is_sent_again_after_master_power_switches() {
+        let mut claw = Manipulator::new(Mode::Position, 2, 1000, 2000, 500.0)
+            .wrist(3, Button::LeftTrigger2, Button::RightTrigger2);
+        step(&mut claw, &[Button::RightTrigger2], 0.1);
+        claw.observe(&RovResponse::MasterOff);
+        assert_eq!(step(&mut claw, &[], 0.1),
+                   vec![RovCommand::ControlServo {
+                            id: 2,
+                            microseconds: 1000,
+                        },
+                        RovCommand::ControlServo {
+                            id: 3,
+                            microseconds: 1550,
+                        }]);
+        assert!(step(&mut claw, &[], 0.1).is_empty());
+    }
+}
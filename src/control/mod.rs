@@ -1,15 +1,36 @@
 
 pub mod motor;
-pub mod lights;
-pub mod master;
 pub mod servo;
+pub mod thrusters;
+pub mod gain;
+pub mod hold;
+pub mod pid;
+pub mod attitude;
+pub mod arming;
+pub mod binding;
+pub mod bench;
+pub mod manipulator;
+pub mod lights;
+pub mod sequence;
+pub mod power;
+pub mod trim;
+pub mod cruise;
 
-use gilrs;
-use rov::RovCommand;
+use input::InputState;
+use rov::{RovCommand, RovResponse};
 
 pub const INT_MAX: f32 = 32767.0;
 
 pub trait Control {
-    fn update(&mut self, input: &gilrs::GamepadState, delta: f64);
+    fn update(&mut self, input: &InputState, delta: f64);
     fn write_commands(&self, output: &mut Vec<RovCommand>);
+
+    /// Short notes for the HUD about anything unusual the control is doing.
+    fn write_status(&self, _output: &mut Vec<String>) {}
+
+    /// Follow what the vehicle reports, e.g. parts it resets by itself.
+    fn observe(&mut self, _response: &RovResponse) {}
+
+    /// Switch off whatever the control has left on, before it is replaced.
+    fn release(&mut self, _output: &mut Vec<RovCommand>) {}
 }
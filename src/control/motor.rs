@@ -1,6 +1,5 @@
 
-use super::Control;
-use gilrs;
+use super::thrusters::Wrench;
 use rov::RovCommand;
 use vecmath;
 
@@ -8,6 +7,9 @@ pub struct MotorBuilder {
     pub id: Option<u8>,
     pub position: Option<[f32; 3]>,
     pub direction: Option<[f32; 3]>,
+    pub acceleration: Option<f32>,
+    pub deceleration: Option<f32>,
+    pub calibration: Option<Calibration>,
 }
 
 impl MotorBuilder {
@@ -16,6 +18,9 @@ impl MotorBuilder {
             id: None,
             position: None,
             direction: None,
+            acceleration: None,
+            deceleration: None,
+            calibration: None,
         }
     }
 
@@ -34,6 +39,23 @@ impl MotorBuilder {
         self
     }
 
+    /// How fast the thrust may move away from zero, in full throttles per second.
+    pub fn acceleration(mut self, acceleration: f32) -> Self {
+        self.acceleration = Some(acceleration);
+        self
+    }
+
+    /// How fast the thrust may fall back toward zero, in full throttles per second.
+    pub fn deceleration(mut self, deceleration: f32) -> Self {
+        self.deceleration = Some(deceleration);
+        self
+    }
+
+    pub fn calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
     pub fn build(self) -> Motor {
         let direction = self.direction.unwrap_or([1.0, 0.0, 0.0]);
         let angle = (direction[1] / direction[0]).tan();
@@ -47,43 +69,117 @@ impl MotorBuilder {
             }
             _ => 0.0,
         };
+        // The moment the motor makes about the vehicle's centre
+        let torque = vecmath::vec3_cross(self.position.unwrap_or([0.0; 3]), direction);
         Motor {
             id: self.id.unwrap_or(0),
             direction: direction,
             rotation_coefficient: rotation_coefficient,
+            roll_coefficient: torque[0],
+            pitch_coefficient: torque[1],
+            acceleration: self.acceleration.unwrap_or(::std::f32::INFINITY),
+            deceleration: self.deceleration.unwrap_or(::std::f32::INFINITY),
+            calibration: self.calibration.unwrap_or_default(),
             thrust: 0,
             prev_thrust: 0,
+            level: 0.0,
+            limiting: false,
+        }
+    }
+}
+
+/// How one motor's ESC and propeller respond, applied to the thrust just before it is sent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    /// The motor is mounted or wired so that it pushes the other way.
+    pub reversed: bool,
+    /// Multiplies forward and reverse thrust, to even out a propeller that is stronger one way.
+    pub forward_scale: f32,
+    pub reverse_scale: f32,
+    /// Fraction of the range around the middle in which the ESC doesn't spin the motor. Any
+    /// thrust other than zero skips over it.
+    pub deadband: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Calibration {
+        Calibration {
+            reversed: false,
+            forward_scale: 1.0,
+            reverse_scale: 1.0,
+            deadband: 0.0,
         }
     }
 }
 
+impl Calibration {
+    pub fn apply(&self, level: f32) -> f32 {
+        let level = if self.reversed { -level } else { level };
+        if level == 0.0 {
+            return 0.0;
+        }
+        let scale = if level > 0.0 { self.forward_scale } else { self.reverse_scale };
+        let magnitude = (level.abs() * scale).min(1.0);
+        level.signum() * (self.deadband + magnitude * (1.0 - self.deadband))
+    }
+}
+
 pub struct Motor {
     // info
     pub id: u8,
     pub direction: [f32; 3],
     pub rotation_coefficient: f32,
+    pub roll_coefficient: f32,
+    pub pitch_coefficient: f32,
+    pub acceleration: f32,
+    pub deceleration: f32,
+    pub calibration: Calibration,
     // state
     pub thrust: i16,
     pub prev_thrust: i16,
+    // thrust before it is rounded for sending, so that slewing doesn't accumulate rounding errors
+    level: f32,
+    /// Whether the thrust is being held back from where the demand wants it
+    pub limiting: bool,
 }
 
-impl Control for Motor {
-    fn update(&mut self, input: &gilrs::GamepadState, _delta: f64) {
+impl Motor {
+    /// This motor's share of the demand, before any limits are applied.
+    pub fn mix(&self, demand: &Wrench) -> f32 {
+        let control_vector = [demand.surge, demand.sway, demand.heave];
+        let thrust = vecmath::vec3_dot(control_vector, self.direction);
+        let thrust = thrust + (demand.yaw * self.rotation_coefficient);
+        let thrust = thrust + (demand.roll * self.roll_coefficient) +
+                     (demand.pitch * self.pitch_coefficient);
+        thrust.max(-1.0).min(1.0)
+    }
+
+    /// Slew toward a thrust in -1..1 that has already been worked out.
+    pub fn update_to(&mut self, target: f32, delta: f64) {
         self.prev_thrust = self.thrust;
-        let forward = input.value(gilrs::Axis::RightStickY);
-        let sideways = input.value(gilrs::Axis::LeftStickX);
-        let ascent = input.value(gilrs::Axis::LeftTrigger2);
-        let descent = input.value(gilrs::Axis::RightTrigger2);
-        let rotational = input.value(gilrs::Axis::RightStickX);
 
-        let control_vector = [forward, sideways, ascent - descent];
-        let thrust = vecmath::vec3_dot(control_vector, self.direction);
-        let thrust = thrust + (rotational * self.rotation_coefficient);
-        let thrust = thrust.max(-1.0).min(1.0);
-        self.thrust = (thrust * super::INT_MAX) as i16;
+        self.level = slew(self.level,
+                          target,
+                          self.acceleration,
+                          self.deceleration,
+                          delta as f32);
+        self.limiting = (self.level - target).abs() > 1.0 / super::INT_MAX;
+        self.thrust = (self.calibration.apply(self.level) * super::INT_MAX) as i16;
     }
 
-    fn write_commands(&self, output: &mut Vec<RovCommand>) {
+    /// Cut the thrust without slewing and send it whether or not it changed.
+    pub fn stop(&mut self, output: &mut Vec<RovCommand>) {
+        self.level = 0.0;
+        self.thrust = 0;
+        self.prev_thrust = 0;
+        self.limiting = false;
+        output.push(RovCommand::ControlMotor {
+            id: self.id,
+            throttle: 0,
+        });
+    }
+
+    pub fn write_commands(&self, output: &mut Vec<RovCommand>) {
         use rov::RovCommand::ControlMotor;
         if self.thrust != self.prev_thrust {
             output.push(ControlMotor {
@@ -92,4 +188,129 @@ impl Control for Motor {
             });
         }
     }
+
+    pub fn write_status(&self, output: &mut Vec<String>) {
+        if self.limiting {
+            output.push(format!("Motor {} ramping", self.id));
+        }
+    }
+}
+
+/// Move `current` toward `target` without exceeding the given rates. Any part of the move that
+/// brings the thrust back toward zero uses the deceleration.
+fn slew(current: f32, target: f32, acceleration: f32, deceleration: f32, delta: f32) -> f32 {
+    let mut current = current;
+    let mut remaining = delta;
+
+    if current != 0.0 && (target.signum() != current.signum() || target.abs() < current.abs()) {
+        let goal = if target.signum() == current.signum() { target } else { 0.0 };
+        let step = deceleration * remaining;
+        let distance = (goal - current).abs();
+        if step < distance {
+            return current + step * (goal - current).signum();
+        }
+        remaining -= distance / deceleration;
+        current = goal;
+    }
+
+    let step = acceleration * remaining;
+    let distance = (target - current).abs();
+    if remaining > 0.0 && step < distance {
+        current + step * (target - current).signum()
+    } else {
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forward_motor() -> Motor {
+        MotorBuilder::new()
+            .id(0)
+            .position([-1.0, 1.0, 0.0])
+            .direction([1.0, 0.0, 0.0])
+            .build()
+    }
+
+    #[test]
+    fn thrust_follows_demand() {
+        let mut motor = forward_motor();
+
+        let target = motor.mix(&Wrench { surge: 0.5, ..Wrench::default() });
+        motor.update_to(target, 0.005);
+        assert_eq!(motor.thrust, (0.5 * ::control::INT_MAX) as i16);
+
+        let mut commands = vec![];
+        motor.write_commands(&mut commands);
+        assert_eq!(commands.len(), 1);
+    }
+
+    #[test]
+    fn unchanged_thrust_is_not_resent() {
+        let mut motor = forward_motor();
+
+        motor.update_to(0.0, 0.005);
+        let mut commands = vec![];
+        motor.write_commands(&mut commands);
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn thrust_ramps_up_slower_than_it_falls() {
+        let mut motor = MotorBuilder::new()
+            .direction([1.0, 0.0, 0.0])
+            .acceleration(2.0)
+            .deceleration(10.0)
+            .build();
+
+        motor.update_to(1.0, 0.25);
+        assert!((motor.thrust as f32 / ::control::INT_MAX - 0.5).abs() < 1e-3);
+        assert!(motor.limiting);
+
+        motor.update_to(1.0, 0.25);
+        assert_eq!(motor.thrust, 32767);
+        assert!(!motor.limiting);
+
+        // Reversing spends 0.1 s falling to zero and the rest rising the other way
+        motor.update_to(-1.0, 0.2);
+        assert!((motor.thrust as f32 / ::control::INT_MAX + 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn vertical_motor_ignores_surge() {
+        let motor = MotorBuilder::new().id(2).direction([0.0, 0.0, -1.0]).build();
+
+        assert_eq!(motor.mix(&Wrench { surge: 1.0, ..Wrench::default() }), 0.0);
+        assert_eq!(motor.mix(&Wrench { heave: 1.0, ..Wrench::default() }), -1.0);
+    }
+
+    #[test]
+    fn calibration_reverses_scales_and_skips_the_deadband() {
+        let calibration = Calibration {
+            reversed: true,
+            forward_scale: 1.0,
+            reverse_scale: 0.5,
+            deadband: 0.1,
+        };
+        assert_eq!(calibration.apply(0.0), 0.0);
+        assert!((calibration.apply(-1.0) - 1.0).abs() < 1e-6);
+        assert!((calibration.apply(0.5) + 0.325).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vertical_motors_off_centre_roll_the_vehicle() {
+        let vertical = |y| {
+            MotorBuilder::new()
+                .position([0.0, y, 1.0])
+                .direction([0.0, 0.0, -1.0])
+                .build()
+        };
+        let (left, right) = (vertical(-1.0), vertical(1.0));
+        let roll = Wrench { roll: 0.5, ..Wrench::default() };
+        assert_eq!(left.mix(&roll), 0.5);
+        assert_eq!(right.mix(&roll), -0.5);
+        assert_eq!(left.mix(&Wrench { pitch: 0.5, ..Wrench::default() }), 0.0);
+    }
 }
@@ -0,0 +1,93 @@
+/// A PID loop whose output is clamped to `-output_limit..output_limit`.
+///
+/// The derivative is taken on the measurement rather than the error, so moving the setpoint
+/// doesn't kick the output.
+pub struct Pid {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub output_limit: f32,
+    // state
+    integral: f32,
+    prev_measurement: Option<f32>,
+}
+
+impl Pid {
+    pub fn new(kp: f32, ki: f32, kd: f32, output_limit: f32) -> Pid {
+        Pid {
+            kp: kp,
+            ki: ki,
+            kd: kd,
+            output_limit: output_limit,
+            integral: 0.0,
+            prev_measurement: None,
+        }
+    }
+
+    /// Forget the accumulated history, e.g. when the loop is engaged again or its mode changes.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.prev_measurement = None;
+    }
+
+    pub fn update(&mut self, setpoint: f32, measurement: f32, delta: f32) -> f32 {
+        let error = setpoint - measurement;
+        let derivative = match self.prev_measurement {
+            Some(prev_measurement) if delta > 0.0 => -(measurement - prev_measurement) / delta,
+            _ => 0.0,
+        };
+        self.prev_measurement = Some(measurement);
+
+        // Only integrate while the output isn't saturated, so the integral can't wind up
+        let integral = self.integral + error * delta;
+        let output = self.kp * error + self.ki * integral + self.kd * derivative;
+        if output.abs() < self.output_limit {
+            self.integral = integral;
+        }
+
+        let output = self.kp * error + self.ki * self.integral + self.kd * derivative;
+        output.max(-self.output_limit).min(self.output_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proportional_output_is_clamped() {
+        let mut pid = Pid::new(2.0, 0.0, 0.0, 1.0);
+        assert_eq!(pid.update(0.25, 0.0, 0.1), 0.5);
+        assert_eq!(pid.update(5.0, 0.0, 0.1), 1.0);
+        assert_eq!(pid.update(-5.0, 0.0, 0.1), -1.0);
+    }
+
+    #[test]
+    fn moving_the_setpoint_does_not_kick_the_derivative() {
+        let mut pid = Pid::new(0.0, 0.0, 1.0, 10.0);
+        pid.update(0.0, 0.0, 0.1);
+        assert_eq!(pid.update(5.0, 0.0, 0.1), 0.0);
+
+        // The measurement rising is damped
+        assert!((pid.update(5.0, 0.1, 0.1) + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn integral_does_not_wind_up_while_saturated() {
+        let mut pid = Pid::new(0.0, 1.0, 0.0, 1.0);
+        for _ in 0..100 {
+            pid.update(10.0, 0.0, 0.1);
+        }
+        // Had the integral kept growing, it would take many steps to come back down
+        assert!(pid.update(-1.0, 0.0, 0.1) < 1.0);
+    }
+
+    #[test]
+    fn reset_clears_history() {
+        let mut pid = Pid::new(0.0, 1.0, 1.0, 10.0);
+        pid.update(1.0, 0.0, 1.0);
+        pid.update(1.0, 0.5, 1.0);
+        pid.reset();
+        assert_eq!(pid.update(0.0, 0.0, 1.0), 0.0);
+    }
+}
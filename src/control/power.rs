@@ -0,0 +1,140 @@
+/// Keeps the current all the motors draw together within what the tether can deliver, by scaling
+/// every motor's thrust by the same amount.
+///
+/// Without a curve the draw is the sum of the absolute thrusts, so a budget of 2 allows two
+/// motors at full throttle. With a curve, it is the amps each motor draws at evenly spaced
+/// thrusts from 0 to full, in either direction, and the budget is in amps.
+
+use super::motor::Calibration;
+pub struct PowerBudget {
+    // info
+    budget: f32,
+    curve: Vec<f32>,
+    // state
+    /// What the thrusts were last multiplied by, in 0..1
+    pub scale: f32,
+}
+
+impl PowerBudget {
+    pub fn new(budget: f32, curve: Vec<f32>) -> Self {
+        PowerBudget {
+            budget: budget.max(0.0),
+            curve: curve,
+            scale: 1.0,
+        }
+    }
+
+    pub fn is_limiting(&self) -> bool {
+        self.scale < 1.0
+    }
+
+    /// Estimated draw of one motor at a thrust in -1..1.
+    fn motor_draw(&self, thrust: f32) -> f32 {
+        let thrust = thrust.abs().min(1.0);
+        if self.curve.len() < 2 {
+            return thrust;
+        }
+        let position = thrust * (self.curve.len() - 1) as f32;
+        let index = (position as usize).min(self.curve.len() - 2);
+        let fraction = position - index as f32;
+        self.curve[index] + (self.curve[index + 1] - self.curve[index]) * fraction
+    }
+
+    pub fn draw(&self, thrusts: &[f32]) -> f32 {
+        thrusts.iter().map(|&thrust| self.motor_draw(thrust)).sum()
+    }
+
+    /// Whether the motors draw less than the budget while stopped. If they don't, no scaling
+    /// can bring them within it.
+    pub fn covers_idle(&self, motors: usize) -> bool {
+        self.draw(&vec![0.0; motors]) < self.budget
+    }
+
+    /// Scale the thrusts down together until their draw fits the budget. The draw is worked out
+    /// from what the ESCs are sent, after each motor's calibration, which may scale the thrust up
+    /// or lift it over a deadband.
+    pub fn limit(&mut self, thrusts: &mut [f32], calibrations: &[Calibration]) {
+        let draw = |scale: f32| -> f32 {
+            thrusts.iter()
+                .zip(calibrations.iter())
+                .map(|(&thrust, calibration)| self.motor_draw(calibration.apply(thrust * scale)))
+                .sum()
+        };
+        let mut scale = 1.0;
+        if draw(1.0) > self.budget {
+            // Neither the curve nor the calibration need be a straight line, so search for the
+            // largest scale that fits
+            let (mut low, mut high) = (0.0, 1.0);
+            for _ in 0..30 {
+                let middle = (low + high) / 2.0;
+                if draw(middle) <= self.budget {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            scale = low;
+        }
+        self.scale = scale;
+        for thrust in thrusts.iter_mut() {
+            *thrust *= scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thrusts_are_scaled_together_to_fit_the_budget() {
+        let mut power = PowerBudget::new(2.0, vec![]);
+        let mut thrusts = [1.0, -1.0, 0.5, 0.5];
+        power.limit(&mut thrusts, &[Calibration::default(); 4]);
+        assert!(power.is_limiting());
+        assert!((power.scale - 2.0 / 3.0).abs() < 1e-6);
+        assert!((thrusts[2] - 1.0 / 3.0).abs() < 1e-6);
+        assert!((power.draw(&thrusts) - 2.0).abs() < 1e-5);
+
+        let mut thrusts = [0.5, 0.5, 0.0, 0.0];
+        power.limit(&mut thrusts, &[Calibration::default(); 4]);
+        assert!(!power.is_limiting());
+        assert_eq!(thrusts, [0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn current_curve_is_interpolated() {
+        let mut power = PowerBudget::new(10.0, vec![0.0, 2.0, 10.0]);
+        assert_eq!(power.draw(&[0.25, -1.0]), 11.0);
+
+        let mut thrusts = [1.0, 1.0];
+        power.limit(&mut thrusts, &[Calibration::default(); 2]);
+        assert!(power.draw(&thrusts) <= 10.0);
+        assert!(power.draw(&thrusts) > 9.99);
+        assert_eq!(thrusts[0], thrusts[1]);
+    }
+
+    #[test]
+    fn calibrated_thrust_is_what_counts() {
+        let mut power = PowerBudget::new(1.0, vec![]);
+        let calibration = Calibration {
+            forward_scale: 2.0,
+            deadband: 0.2,
+            ..Calibration::default()
+        };
+        let mut thrusts = [0.5, 0.5];
+        power.limit(&mut thrusts, &[calibration; 2]);
+        assert!(power.is_limiting());
+        let sent = calibration.apply(thrusts[0]) * 2.0;
+        assert!(sent <= 1.0);
+        assert!(sent > 0.999);
+    }
+
+    #[test]
+    fn budget_must_cover_the_idle_draw() {
+        let power = PowerBudget::new(2.0, vec![0.6, 2.0, 10.0]);
+        assert!(power.covers_idle(3));
+        assert!(!power.covers_idle(4));
+        assert!(PowerBudget::new(0.5, vec![]).covers_idle(4));
+    }
+}
@@ -0,0 +1,176 @@
+use super::Control;
+use super::thrusters::Wrench;
+use input::{Button, InputState};
+use rov::RovCommand;
+
+/// How far the pilot may move a stick before a running sequence lets go.
+const STICK_MOVED: f32 = 0.1;
+
+/// One timed part of a sequence.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Step {
+    /// In seconds
+    pub duration: f64,
+    /// Added to the pilot's demand for the length of the step
+    pub demand: Wrench,
+    /// Sent when the step starts
+    pub commands: Vec<RovCommand>,
+}
+
+/// Runs timed steps one after another when the button is pressed, e.g. "descend for 2 s, turn,
+/// tilt the camera down and switch the lights on". Pressing the button again, or moving a stick,
+/// stops it.
+pub struct Sequence {
+    // info
+    pub name: String,
+    button: Button,
+    steps: Vec<Step>,
+    // state
+    /// The step being run and how long it has run for
+    running: Option<(usize, f64)>,
+    pub demand: Wrench,
+    pending: Vec<RovCommand>,
+}
+
+impl Sequence {
+    pub fn new(name: String, button: Button, steps: Vec<Step>) -> Self {
+        Sequence {
+            name: name,
+            button: button,
+            steps: steps,
+            running: None,
+            demand: Wrench::default(),
+            pending: vec![],
+        }
+    }
+
+    /// The step being run, counting from one, and the number of steps.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.running.map(|(index, _)| (index + 1, self.steps.len()))
+    }
+
+    pub fn abort(&mut self) {
+        if self.running.take().is_some() {
+            info!("Sequence stopped"; "name" => &self.name);
+        }
+        self.demand = Wrench::default();
+    }
+
+    fn start_step(&mut self, index: usize, elapsed: f64) {
+        match self.steps.get(index) {
+            Some(step) => {
+                self.pending.extend(step.commands.iter().cloned());
+                self.running = Some((index, elapsed));
+            }
+            None => {
+                info!("Sequence finished"; "name" => &self.name);
+                self.running = None;
+            }
+        }
+    }
+}
+
+impl Control for Sequence {
+    fn update(&mut self, input: &InputState, delta: f64) {
+        self.pending.clear();
+        let pilot = Wrench::from_input(input);
+        let moved = [pilot.surge, pilot.sway, pilot.heave, pilot.yaw]
+            .iter()
+            .any(|value| value.abs() > STICK_MOVED);
+
+        if self.running.is_some() && (moved || input.just_pressed(self.button)) {
+            self.abort();
+            return;
+        }
+        if input.just_pressed(self.button) {
+            info!("Sequence started"; "name" => &self.name);
+            self.start_step(0, 0.0);
+        } else if let Some((index, elapsed)) = self.running {
+            self.running = Some((index, elapsed + delta));
+        }
+        // Steps with no duration only send their commands
+        while let Some((index, elapsed)) = self.running {
+            let duration = self.steps[index].duration;
+            if elapsed < duration {
+                break;
+            }
+            self.start_step(index + 1, elapsed - duration);
+        }
+
+        self.demand = match self.running {
+            Some((index, _)) => self.steps[index].demand,
+            None => Wrench::default(),
+        };
+    }
+
+    fn write_commands(&self, output: &mut Vec<RovCommand>) {
+        output.extend(self.pending.iter().cloned());
+    }
+
+    fn write_status(&self, output: &mut Vec<String>) {
+        if let Some((step, steps)) = self.progress() {
+            output.push(format!("Running {}: step {} of {}", self.name, step, steps));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::Axis;
+
+    fn steps() -> Vec<Step> {
+        vec![Step {
+                 duration: 2.0,
+                 demand: Wrench { heave: -0.5, ..Wrench::default() },
+                 commands: vec![],
+             },
+             Step {
+                 duration: 0.0,
+                 demand: Wrench::default(),
+                 commands: vec![RovCommand::LightsOn],
+             },
+             Step {
+                 duration: 1.0,
+                 demand: Wrench { yaw: 0.5, ..Wrench::default() },
+                 commands: vec![],
+             }]
+    }
+
+    #[test]
+    fn steps_run_in_turn_and_send_their_commands() {
+        let mut sequence = Sequence::new("Dive".into(), Button::Mode, steps());
+        let mut input = InputState::new();
+        input.press(Button::Mode);
+        sequence.update(&input, 0.1);
+        assert_eq!(sequence.demand.heave, -0.5);
+        assert_eq!(sequence.progress(), Some((1, 3)));
+
+        input.advance();
+        sequence.update(&input, 2.5);
+        let mut commands = vec![];
+        sequence.write_commands(&mut commands);
+        assert_eq!(commands, vec![RovCommand::LightsOn]);
+        assert_eq!(sequence.progress(), Some((3, 3)));
+        assert_eq!(sequence.demand.yaw, 0.5);
+
+        input.advance();
+        sequence.update(&input, 1.0);
+        assert_eq!(sequence.progress(), None);
+        assert_eq!(sequence.demand, Wrench::default());
+    }
+
+    #[test]
+    fn moving_a_stick_stops_the_sequence() {
+        let mut sequence = Sequence::new("Dive".into(), Button::Mode, steps());
+        let mut input = InputState::new();
+        input.press(Button::Mode);
+        sequence.update(&input, 0.1);
+
+        input.advance();
+        input.set_value(Axis::RightStickY, 0.3);
+        sequence.update(&input, 0.1);
+        assert_eq!(sequence.progress(), None);
+        assert_eq!(sequence.demand, Wrench::default());
+    }
+}
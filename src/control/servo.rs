@@ -1,7 +1,6 @@
-
 use super::Control;
-use gilrs;
-use rov::RovCommand;
+use input::{Button, InputState};
+use rov::{RovCommand, RovResponse};
 
 pub const SERVO_LOW: i16 = 1000;
 pub const SERVO_MID: i16 = 1500;
@@ -12,18 +11,31 @@ pub const DEFAULT_MOVE_SPEED: f64 = (SERVO_HIGH as f64 - SERVO_LOW as f64) / SEC
 pub struct Servo {
     // info
     pub id: u8,
-    increase_button: gilrs::Button,
-    decrease_button: gilrs::Button,
+    increase_button: Button,
+    decrease_button: Button,
     move_speed: f64,
+    /// Mechanical limits, within `SERVO_LOW..SERVO_HIGH`
+    pub min: i16,
+    pub max: i16,
+    /// Offset of the centre from `SERVO_MID`
+    pub trim: i16,
+    recenter_button: Option<Button>,
+    presets: Vec<(Button, i16)>,
     // state
+    /// Where the servo is, kept unrounded so that small steps at the control rate add up
+    position: f64,
     pub microseconds: i16,
     pub prev_microseconds: i16,
+    /// Where the servo is moving to on its own, after a preset or recenter
+    pub target: Option<i16>,
+    /// Whether the ROV has put the servo back to `SERVO_MID` since the last update
+    reset: bool,
 }
 
 impl Servo {
     pub fn new(id: u8,
-               increase_button: gilrs::Button,
-               decrease_button: gilrs::Button,
+               increase_button: Button,
+               decrease_button: Button,
                move_speed: f64)
                -> Self {
         Servo {
@@ -31,29 +43,103 @@ impl Servo {
             increase_button: increase_button,
             decrease_button: decrease_button,
             move_speed: move_speed,
+            min: SERVO_LOW,
+            max: SERVO_HIGH,
+            trim: 0,
+            recenter_button: None,
+            presets: vec![],
+            position: SERVO_MID as f64,
             microseconds: SERVO_MID,
             prev_microseconds: SERVO_MID,
+            target: None,
+            reset: false,
         }
     }
+
+    /// Limit the servo to its mechanical range and offset its centre.
+    pub fn calibrate(mut self, min: i16, max: i16, trim: i16) -> Self {
+        self.min = min.max(SERVO_LOW);
+        self.max = max.min(SERVO_HIGH);
+        self.trim = trim;
+        let centre = self.centre();
+        self.move_to(centre);
+        self.prev_microseconds = self.microseconds;
+        self
+    }
+
+    pub fn recenter_button(mut self, button: Button) -> Self {
+        self.recenter_button = Some(button);
+        self
+    }
+
+    /// Move smoothly to the position when the button is pressed.
+    pub fn preset(mut self, button: Button, microseconds: i16) -> Self {
+        self.presets.push((button, microseconds));
+        self
+    }
+
+    pub fn centre(&self) -> i16 {
+        self.clamp(SERVO_MID + self.trim)
+    }
+
+    pub fn clamp(&self, microseconds: i16) -> i16 {
+        microseconds.max(self.min).min(self.max)
+    }
+
+    /// Move by an amount regardless of the calibrated range, so that its limits can be found.
+    pub fn jog(&mut self, amount: i16) {
+        self.target = None;
+        let microseconds = (self.microseconds + amount).max(SERVO_LOW).min(SERVO_HIGH);
+        self.move_to(microseconds);
+    }
+
+    fn move_to(&mut self, microseconds: i16) {
+        self.position = microseconds as f64;
+        self.microseconds = microseconds;
+    }
 }
 
 impl Control for Servo {
-    fn update(&mut self, input: &gilrs::GamepadState, delta: f64) {
-        self.prev_microseconds = self.microseconds;
+    fn update(&mut self, input: &InputState, delta: f64) {
+        // Once reset, the servo is sent where it should be even if it hasn't moved
+        self.prev_microseconds = if self.reset { SERVO_MID } else { self.microseconds };
+        self.reset = false;
         let increase = input.is_pressed(self.increase_button);
         let decrease = input.is_pressed(self.decrease_button);
+        let step = self.move_speed * delta;
+        let (min, max) = (self.min as f64, self.max as f64);
 
-        self.microseconds = match (increase, decrease) {
-            (true, false) => self.microseconds + (self.move_speed * delta) as i16,
-            (false, true) => self.microseconds - (self.move_speed * delta) as i16,
-            _ => self.microseconds,
-        };
-        if self.microseconds < SERVO_LOW {
-            self.microseconds = SERVO_LOW;
+        if self.recenter_button.map_or(false, |button| input.just_pressed(button)) {
+            self.target = Some(self.centre());
         }
-        if self.microseconds > SERVO_HIGH {
-            self.microseconds = SERVO_HIGH;
+        for &(button, position) in self.presets.iter() {
+            if input.just_pressed(button) {
+                self.target = Some(self.clamp(position));
+            }
         }
+
+        self.position = match (increase, decrease) {
+            (true, false) => {
+                self.target = None;
+                (self.position + step).max(min).min(max)
+            }
+            (false, true) => {
+                self.target = None;
+                (self.position - step).max(min).min(max)
+            }
+            _ => {
+                match self.target {
+                    Some(target) if (target as f64 - self.position).abs() <= step => {
+                        self.target = None;
+                        target as f64
+                    }
+                    Some(target) if target as f64 > self.position => self.position + step,
+                    Some(_) => self.position - step,
+                    None => self.position,
+                }
+            }
+        };
+        self.microseconds = self.position.round() as i16;
     }
 
     fn write_commands(&self, output: &mut Vec<RovCommand>) {
@@ -65,4 +151,104 @@ impl Control for Servo {
             });
         }
     }
+
+    /// The ROV puts the servos to `SERVO_MID` whenever master power is switched, which takes no
+    /// account of their trim.
+    fn observe(&mut self, response: &RovResponse) {
+        match *response {
+            RovResponse::MasterOn | RovResponse::MasterOff => self.reset = true,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_at_speed_and_stops_at_limits() {
+        let mut servo = Servo::new(0, Button::DPadUp, Button::DPadDown, 100.0);
+        let mut input = InputState::new();
+        input.press(Button::DPadUp);
+
+        servo.update(&input, 0.5);
+        assert_eq!(servo.microseconds, SERVO_MID + 50);
+        let mut commands = vec![];
+        servo.write_commands(&mut commands);
+        assert_eq!(commands.len(), 1);
+
+        servo.update(&input, 100.0);
+        assert_eq!(servo.microseconds, SERVO_HIGH);
+    }
+
+    #[test]
+    fn slow_moves_add_up_at_the_control_rate() {
+        let mut servo = Servo::new(0, Button::DPadUp, Button::DPadDown, 150.0);
+        let mut input = InputState::new();
+        input.press(Button::DPadUp);
+
+        for _ in 0..200 {
+            servo.update(&input, 0.005);
+        }
+        assert_eq!(servo.microseconds, SERVO_MID + 150);
+    }
+
+    #[test]
+    fn opposing_buttons_cancel_out() {
+        let mut servo = Servo::new(0, Button::DPadUp, Button::DPadDown, 100.0);
+        let mut input = InputState::new();
+        input.press(Button::DPadUp);
+        input.press(Button::DPadDown);
+
+        servo.update(&input, 0.5);
+        let mut commands = vec![];
+        servo.write_commands(&mut commands);
+        assert_eq!(servo.microseconds, SERVO_MID);
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn presets_and_recenter_move_smoothly_within_range() {
+        let mut servo = Servo::new(0, Button::DPadUp, Button::DPadDown, 100.0)
+            .calibrate(1200, 1800, 50)
+            .recenter_button(Button::RightThumb)
+            .preset(Button::South, 1000);
+        assert_eq!(servo.microseconds, 1550);
+
+        let mut input = InputState::new();
+        input.press(Button::South);
+        servo.update(&input, 1.0);
+        assert_eq!(servo.microseconds, 1450);
+        input.advance();
+        servo.update(&input, 10.0);
+        assert_eq!(servo.microseconds, 1200);
+        assert_eq!(servo.target, None);
+
+        input.advance();
+        input.press(Button::RightThumb);
+        servo.update(&input, 10.0);
+        assert_eq!(servo.microseconds, 1550);
+    }
+
+    #[test]
+    fn trimmed_position_is_sent_again_after_master_power_switches() {
+        let mut servo = Servo::new(0, Button::DPadUp, Button::DPadDown, 100.0)
+            .calibrate(1000, 2000, 50);
+        let input = InputState::new();
+        servo.observe(&RovResponse::MasterOn);
+        servo.update(&input, 0.005);
+        let mut commands = vec![];
+        servo.write_commands(&mut commands);
+        assert_eq!(commands,
+                   vec![RovCommand::ControlServo {
+                            id: 0,
+                            microseconds: 1550,
+                        }]);
+
+        servo.update(&input, 0.005);
+        commands.clear();
+        servo.write_commands(&mut commands);
+        assert!(commands.is_empty());
+    }
 }
@@ -0,0 +1,325 @@
+use super::Control;
+use super::attitude::Stabiliser;
+use super::cruise::Cruise;
+use super::gain::Gain;
+use super::hold::Hold;
+use super::motor::Motor;
+use super::power::PowerBudget;
+use input::{Axis, InputState};
+use rov::RovCommand;
+use vehicle::{orientation_degrees, VehicleState};
+
+/// What is asked of the vehicle as a whole. Each part is in -1..1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Wrench {
+    pub surge: f32,
+    pub sway: f32,
+    pub heave: f32,
+    pub yaw: f32,
+    pub roll: f32,
+    pub pitch: f32,
+}
+
+impl Wrench {
+    /// Read the pilot's demand off the sticks and triggers.
+    pub fn from_input(input: &InputState) -> Wrench {
+        Wrench {
+            surge: input.value(Axis::RightStickY),
+            sway: input.value(Axis::LeftStickX),
+            heave: input.value(Axis::LeftTrigger2) - input.value(Axis::RightTrigger2),
+            yaw: input.value(Axis::RightStickX),
+            ..Wrench::default()
+        }
+    }
+
+    pub fn scaled(&self, translation: f32, rotation: f32) -> Wrench {
+        Wrench {
+            surge: self.surge * translation,
+            sway: self.sway * translation,
+            heave: self.heave * translation,
+            yaw: self.yaw * rotation,
+            roll: self.roll * rotation,
+            pitch: self.pitch * rotation,
+        }
+    }
+}
+
+impl ::std::ops::Add for Wrench {
+    type Output = Wrench;
+
+    fn add(self, other: Wrench) -> Wrench {
+        Wrench {
+            surge: self.surge + other.surge,
+            sway: self.sway + other.sway,
+            heave: self.heave + other.heave,
+            yaw: self.yaw + other.yaw,
+            roll: self.roll + other.roll,
+            pitch: self.pitch + other.pitch,
+        }
+    }
+}
+
+/// Turns the pilot's demand into thrust for every motor.
+pub struct Thrusters {
+    // info
+    pub motors: Vec<Motor>,
+    // state
+    pub gain: Gain,
+    pub heading_hold: Hold,
+    pub depth_hold: Hold,
+    pub stabiliser: Stabiliser,
+    pub demand: Wrench,
+    /// Added to the pilot's demand after the gain, e.g. by a running sequence
+    pub assist: Wrench,
+    /// Constant offsets added after the gain, which unlike `assist` don't let go of the holds
+    pub trim: Wrench,
+    /// Spin only this motor, at this thrust, ignoring the demand
+    pub test: Option<(usize, f32)>,
+    pub power: Option<PowerBudget>,
+    pub cruise: Option<Cruise>,
+}
+
+impl Thrusters {
+    pub fn new(motors: Vec<Motor>,
+               gain: Gain,
+               heading_hold: Hold,
+               depth_hold: Hold,
+               stabiliser: Stabiliser)
+               -> Self {
+        Thrusters {
+            motors: motors,
+            gain: gain,
+            heading_hold: heading_hold,
+            depth_hold: depth_hold,
+            stabiliser: stabiliser,
+            demand: Wrench::default(),
+            assist: Wrench::default(),
+            trim: Wrench::default(),
+            test: None,
+            power: None,
+            cruise: None,
+        }
+    }
+
+    /// Feed the autopilots the latest state reported by the vehicle. Stale readings count as
+    /// missing, so the holds let go rather than act on them.
+    pub fn observe(&mut self, vehicle: &VehicleState) {
+        let orientation = vehicle.compass
+            .current()
+            .and_then(|compass| compass)
+            .map(orientation_degrees);
+        self.heading_hold.observe(orientation.map(|(heading, _, _)| heading));
+        self.stabiliser.observe(orientation.map(|(_, roll, pitch)| (roll, pitch)));
+
+        let depth = if vehicle.pressure.is_stale() {
+            None
+        } else {
+            vehicle.depth()
+        };
+        self.depth_hold.observe(depth);
+    }
+
+    /// Whether any motor can turn the vehicle about each axis, as (roll, pitch).
+    pub fn authority(&self) -> (bool, bool) {
+        let any = |coefficient: &Fn(&Motor) -> f32| {
+            self.motors.iter().any(|motor| coefficient(motor).abs() > 1e-3)
+        };
+        (any(&|motor| motor.roll_coefficient), any(&|motor| motor.pitch_coefficient))
+    }
+
+    /// Stop every motor at once, letting go of the holds.
+    pub fn stop(&mut self, output: &mut Vec<RovCommand>) {
+        self.heading_hold.enabled = false;
+        self.depth_hold.enabled = false;
+        self.stabiliser.enabled = false;
+        if let Some(ref mut cruise) = self.cruise {
+            cruise.cancel();
+        }
+        self.demand = Wrench::default();
+        for motor in self.motors.iter_mut() {
+            motor.stop(output);
+        }
+    }
+}
+
+impl Control for Thrusters {
+    fn update(&mut self, input: &InputState, delta: f64) {
+        self.gain.update(input);
+
+        let mut pilot = Wrench::from_input(input);
+        if let Some(ref mut cruise) = self.cruise {
+            pilot.surge = cruise.update(input, pilot.surge);
+        }
+        let assist = self.assist;
+        let mut demand = self.gain.apply(&pilot) + assist + self.trim;
+        // The holds let go while anything else asks for a turn or a change of depth
+        demand.yaw += self.heading_hold.update(input, pilot.yaw + assist.yaw, delta);
+        demand.heave += self.depth_hold.update(input, pilot.heave + assist.heave, delta);
+        let (roll, pitch) = self.stabiliser.update(input, delta);
+        demand.roll += roll;
+        demand.pitch += pitch;
+        self.demand = demand;
+
+        if let Some((index, thrust)) = self.test {
+            for (i, motor) in self.motors.iter_mut().enumerate() {
+                motor.update_to(if i == index { thrust } else { 0.0 }, delta);
+            }
+            return;
+        }
+        let mut targets: Vec<f32> = self.motors.iter().map(|motor| motor.mix(&demand)).collect();
+        if let Some(ref mut power) = self.power {
+            let calibrations: Vec<_> = self.motors.iter().map(|motor| motor.calibration).collect();
+            power.limit(&mut targets, &calibrations);
+        }
+        for (motor, &target) in self.motors.iter_mut().zip(targets.iter()) {
+            motor.update_to(target, delta);
+        }
+    }
+
+    fn write_commands(&self, output: &mut Vec<RovCommand>) {
+        for motor in self.motors.iter() {
+            motor.write_commands(output);
+        }
+    }
+
+    fn write_status(&self, output: &mut Vec<String>) {
+        if self.stabiliser.enabled {
+            output.push(match self.authority() {
+                (true, true) => "Stabilising roll and pitch".into(),
+                (true, false) => "Stabilising roll".into(),
+                (false, true) => "Stabilising pitch".into(),
+                (false, false) => "No thrusters to stabilise with".into(),
+            });
+        }
+        if self.heading_hold.enabled {
+            output.push(match self.heading_hold.target {
+                Some(target) => format!("Holding heading {:.0}", target),
+                None => "Heading hold ready".into(),
+            });
+        }
+        if self.depth_hold.enabled {
+            output.push(match self.depth_hold.target {
+                Some(target) => format!("Holding depth {:.2} m", target),
+                None => "Depth hold ready".into(),
+            });
+        }
+        if let Some(ref cruise) = self.cruise {
+            cruise.write_status(output);
+        }
+        if let Some(ref power) = self.power {
+            if power.is_limiting() {
+                output.push(format!("Power limited to {:.0}%", power.scale * 100.0));
+            }
+        }
+        for motor in self.motors.iter() {
+            motor.write_status(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::GainMode;
+    use control::motor::MotorBuilder;
+    use control::hold;
+    use control::pid::Pid;
+    use input::Button;
+
+    fn gain_modes() -> Vec<GainMode> {
+        vec![GainMode {
+                 name: "Precision".into(),
+                 translation: 0.3,
+                 rotation: 0.5,
+             },
+             GainMode {
+                 name: "Full".into(),
+                 translation: 1.0,
+                 rotation: 1.0,
+             }]
+    }
+
+    #[test]
+    fn gain_mode_scales_demand_and_cycles() {
+        let motor = MotorBuilder::new().direction([1.0, 0.0, 0.0]).build();
+        let gain = Gain::new(Button::West, gain_modes(), 0);
+        let hold = || Hold::new(Button::East, Pid::new(0.0, 0.0, 0.0, 0.0), hold::heading_error);
+        let stabiliser = Stabiliser::new(Button::LeftThumb,
+                                         Pid::new(0.0, 0.0, 0.0, 0.0),
+                                         Pid::new(0.0, 0.0, 0.0, 0.0));
+        let mut thrusters = Thrusters::new(vec![motor], gain, hold(), hold(), stabiliser);
+        let mut input = InputState::new();
+
+        input.set_value(Axis::RightStickY, 1.0);
+        input.set_value(Axis::RightStickX, 1.0);
+        thrusters.update(&input, 0.005);
+        assert!((thrusters.demand.surge - 0.3).abs() < 1e-6);
+        assert!((thrusters.demand.yaw - 0.5).abs() < 1e-6);
+
+        input.advance();
+        input.set_value(Axis::RightStickY, 1.0);
+        input.press(Button::West);
+        thrusters.update(&input, 0.005);
+        assert_eq!(thrusters.gain.mode().unwrap().name, "Full");
+        assert_eq!(thrusters.motors[0].thrust, 32767);
+
+        input.advance();
+        thrusters.update(&input, 0.005);
+        input.advance();
+        input.press(Button::West);
+        thrusters.update(&input, 0.005);
+        assert_eq!(thrusters.gain.mode().unwrap().name, "Precision");
+    }
+
+    #[test]
+    fn only_axes_the_motors_can_turn_are_stabilised() {
+        let vertical = |position| {
+            MotorBuilder::new().position(position).direction([0.0, 0.0, -1.0])
+        };
+        let stabiliser = || {
+            let mut stabiliser = Stabiliser::new(Button::LeftThumb,
+                                                 Pid::new(0.0, 0.0, 0.0, 0.0),
+                                                 Pid::new(0.0, 0.0, 0.0, 0.0));
+            stabiliser.set_enabled(true);
+            stabiliser
+        };
+        let hold = || Hold::new(Button::East, Pid::new(0.0, 0.0, 0.0, 0.0), hold::heading_error);
+        let gain = || Gain::new(Button::West, gain_modes(), 0);
+
+        let side_by_side = vec![vertical([0.0, -1.0, 1.0]).build(),
+                                vertical([0.0, 1.0, 1.0]).build()];
+        let thrusters = Thrusters::new(side_by_side, gain(), hold(), hold(), stabiliser());
+        assert_eq!(thrusters.authority(), (true, false));
+        let mut status = vec![];
+        thrusters.write_status(&mut status);
+        assert!(status.contains(&"Stabilising roll".to_string()));
+
+        let fore_and_aft = vec![vertical([1.0, -1.0, 1.0]).build(),
+                                vertical([-1.0, 1.0, 1.0]).build()];
+        let thrusters = Thrusters::new(fore_and_aft, gain(), hold(), hold(), stabiliser());
+        assert_eq!(thrusters.authority(), (true, true));
+    }
+
+    #[test]
+    fn depth_hold_engages_with_heave_trim() {
+        let motor = MotorBuilder::new().direction([0.0, 0.0, 1.0]).build();
+        let hold = || Hold::new(Button::East, Pid::new(1.0, 0.0, 0.0, 1.0), hold::depth_error);
+        let stabiliser = Stabiliser::new(Button::LeftThumb,
+                                         Pid::new(0.0, 0.0, 0.0, 0.0),
+                                         Pid::new(0.0, 0.0, 0.0, 0.0));
+        let gain = Gain::new(Button::West, gain_modes(), 1);
+        let mut thrusters = Thrusters::new(vec![motor], gain, hold(), hold(), stabiliser);
+        thrusters.trim = Wrench { heave: -0.08, ..Wrench::default() };
+        thrusters.depth_hold.enabled = true;
+        thrusters.depth_hold.observe(Some(3.0));
+
+        let input = InputState::new();
+        thrusters.update(&input, 0.005);
+        assert_eq!(thrusters.depth_hold.target, Some(3.0));
+        assert!((thrusters.demand.heave + 0.08).abs() < 1e-6);
+
+        thrusters.assist = Wrench { heave: 0.5, ..Wrench::default() };
+        thrusters.update(&input, 0.005);
+        assert_eq!(thrusters.depth_hold.target, None);
+    }
+}
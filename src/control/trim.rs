@@ -0,0 +1,154 @@
+use super::Control;
+use super::thrusters::Wrench;
+use input::{Button, InputState};
+use rov::RovCommand;
+
+/// The largest trim on any axis, as a share of full demand.
+pub const MAX_TRIM: f32 = 0.5;
+/// How long the offset takes to come in after arming, in seconds, so arming doesn't kick the
+/// thrusters.
+const ENGAGE_TIME: f64 = 1.0;
+
+/// Constant offsets added to the demand, e.g. a little downward heave to keep a slightly
+/// buoyant vehicle down without holding a trigger. Each press of a button moves its axis by a
+/// step.
+pub struct Trim {
+    // info
+    step: f32,
+    /// Pressing the first button adds the direction, the second takes it away
+    buttons: Vec<(Button, Button, Wrench)>,
+    // state
+    pub offset: Wrench,
+    /// Whether the last update moved the offset
+    pub changed: bool,
+    /// How much of the offset is pushing, in 0..1
+    pub engaged: f32,
+}
+
+impl Trim {
+    pub fn new(offset: Wrench, step: f32) -> Self {
+        Trim {
+            step: step,
+            buttons: vec![],
+            offset: clamped(offset),
+            changed: false,
+            engaged: 0.0,
+        }
+    }
+
+    pub fn buttons(mut self, increase: Button, decrease: Button, direction: Wrench) -> Self {
+        self.buttons.push((increase, decrease, direction));
+        self
+    }
+
+    /// Ramp the offset in while armed, and drop it straight away when not.
+    pub fn engage(&mut self, armed: bool, delta: f64) {
+        self.engaged = if armed {
+            (self.engaged + (delta / ENGAGE_TIME) as f32).min(1.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// What the trim adds to the demand.
+    pub fn demand(&self) -> Wrench {
+        self.offset.scaled(self.engaged, self.engaged)
+    }
+}
+
+fn clamped(offset: Wrench) -> Wrench {
+    let clamp = |value: f32| value.max(-MAX_TRIM).min(MAX_TRIM);
+    Wrench {
+        heave: clamp(offset.heave),
+        roll: clamp(offset.roll),
+        pitch: clamp(offset.pitch),
+        ..Wrench::default()
+    }
+}
+
+impl Control for Trim {
+    fn update(&mut self, input: &InputState, _delta: f64) {
+        let previous = self.offset;
+        for &(increase, decrease, direction) in self.buttons.iter() {
+            if input.just_pressed(increase) {
+                self.offset = self.offset + direction.scaled(self.step, self.step);
+            }
+            if input.just_pressed(decrease) {
+                self.offset = self.offset + direction.scaled(-self.step, -self.step);
+            }
+        }
+        // Steps are small, so snap to zero rather than leave rounding errors behind
+        let step = self.step;
+        let snap = |value: f32| if value.abs() < step / 2.0 { 0.0 } else { value };
+        let offset = clamped(self.offset);
+        self.offset = Wrench {
+            heave: snap(offset.heave),
+            roll: snap(offset.roll),
+            pitch: snap(offset.pitch),
+            ..Wrench::default()
+        };
+        self.changed = self.offset != previous;
+    }
+
+    fn write_commands(&self, _output: &mut Vec<RovCommand>) {}
+
+    fn write_status(&self, output: &mut Vec<String>) {
+        let axes = [("heave", self.offset.heave),
+                    ("pitch", self.offset.pitch),
+                    ("roll", self.offset.roll)];
+        let trimmed: Vec<String> = axes.iter()
+            .filter(|&&(_, offset)| offset != 0.0)
+            .map(|&(name, offset)| format!("{} {:+.2}", name, offset))
+            .collect();
+        if !trimmed.is_empty() {
+            output.push(format!("Trim {}", trimmed.join(" ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presses_step_the_offset_within_its_limit() {
+        let down = Wrench { heave: -1.0, ..Wrench::default() };
+        let mut trim = Trim::new(Wrench::default(), 0.2)
+            .buttons(Button::North, Button::South, down);
+        let mut input = InputState::new();
+        for _ in 0..4 {
+            input.advance();
+            trim.update(&input, 0.1);
+            input.advance();
+            input.press(Button::North);
+            trim.update(&input, 0.1);
+            assert!(trim.changed || trim.offset.heave == -MAX_TRIM);
+        }
+        assert_eq!(trim.offset.heave, -MAX_TRIM);
+
+        input.advance();
+        trim.update(&input, 0.1);
+        assert!(!trim.changed);
+        for _ in 0..2 {
+            input.advance();
+            input.press(Button::South);
+            trim.update(&input, 0.1);
+            input.advance();
+            trim.update(&input, 0.1);
+        }
+        assert!((trim.offset.heave + 0.1).abs() < 1e-6);
+        assert_eq!(trim.offset.surge, 0.0);
+    }
+
+    #[test]
+    fn offset_ramps_in_after_arming() {
+        let mut trim = Trim::new(Wrench { heave: -0.4, ..Wrench::default() }, 0.1);
+        assert_eq!(trim.demand(), Wrench::default());
+        trim.engage(true, 0.5);
+        assert!((trim.demand().heave + 0.2).abs() < 1e-6);
+        trim.engage(true, 1.0);
+        assert!((trim.demand().heave + 0.4).abs() < 1e-6);
+        trim.engage(false, 0.005);
+        assert_eq!(trim.demand(), Wrench::default());
+    }
+}
@@ -0,0 +1,476 @@
+/// Flies the ROV: the latest samples of the gamepads go in and commands go out to the vehicle,
+/// at a fixed rate on a thread of its own so that drawing the screen can't hold it up.
+
+use rov::{Rov, RovCommand, RovResponse};
+use vehicle::VehicleState;
+use reconcile::Reconciler;
+use control::Control;
+use control::servo::Servo;
+use control::thrusters::{Thrusters, Wrench};
+use control::arming::Arming;
+use control::gain::Gain;
+use control::hold::{self, Hold};
+use control::attitude::Stabiliser;
+use control::cruise::Cruise;
+use control::sequence::Sequence;
+use control::trim::Trim;
+use input::Button;
+use input::InputState;
+use input::roles::Role;
+use input::shaping::Shaping;
+use ::errors::*;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub struct Flight {
+    pub arming: Arming,
+    pub thrusters: Thrusters,
+    pub servos: Vec<Servo>,
+    /// Run by the motors' gamepad, adding to its demand
+    pub sequences: Vec<Sequence>,
+    /// Added to the motors' demand while armed
+    pub trim: Trim,
+    /// Button bindings from the current profile
+    pub controls: Vec<(Role, Box<Control + Send>)>,
+    /// Lights and the gripper, which keep their state when the profile changes
+    pub accessories: Vec<(Role, Box<Control + Send>)>,
+    /// Index into the configured profiles
+    pub profile: usize,
+    shaping: Shaping,
+    rov: Rov,
+    pub vehicle: VehicleState,
+    /// What was stale last time the vehicle reported, to log when it changes
+    stale: Vec<String>,
+    /// Checks the vehicle's echoes against the commands sent
+    pub reconciler: Reconciler,
+    /// The control settings, with trim kept up to date as it is saved
+    config: ::config::Controls,
+    /// The latest samples of the gamepads, handed over by the screen. The co-pilot's is None
+    /// without a co-pilot gamepad.
+    pub pilot_sample: InputState,
+    pub copilot_sample: Option<InputState>,
+    /// When the samples were taken. Older than `sample_timeout`, they are flown as no input so
+    /// a stalled screen can't hold the sticks where they were.
+    pub sampled: Instant,
+    sample_timeout: Duration,
+    stalled: bool,
+    // Input as last ticked, and after shaping
+    pub pilot_raw: InputState,
+    pub copilot_raw: InputState,
+    pub pilot_input: InputState,
+    pub copilot_input: InputState,
+    /// Profiles are only cycled while the HUD is showing
+    pub hud: bool,
+    /// The motor the motors panel wants spun, used while armed
+    pub test: Option<(usize, f32)>,
+    /// Why the control loop stopped, if it did
+    pub error: Option<String>,
+    /// Trim for the screen to write to `config.toml`, with the profile it belongs to
+    pub trim_to_save: Option<(Option<String>, ::config::TrimOffsets)>,
+}
+
+impl Flight {
+    pub fn new(rov: Rov) -> Flight {
+        Flight {
+            arming: Arming::new(Button::Start, 1.0),
+            thrusters: build_thrusters(&::config::Controls::default()),
+            servos: build_servos(&::config::Controls::default()),
+            sequences: vec![],
+            trim: ::config::Trim::default().build(true, true),
+            controls: vec![],
+            accessories: vec![],
+            profile: 0,
+            shaping: Shaping::default(),
+            rov: rov,
+            vehicle: VehicleState::new(&::config::Staleness::default()),
+            stale: vec![],
+            reconciler: Reconciler::new(::config::Reconcile::default().after),
+            config: ::config::Controls::default(),
+            pilot_sample: InputState::new(),
+            copilot_sample: None,
+            sampled: Instant::now(),
+            sample_timeout: sample_timeout(&::config::Timing::default()),
+            stalled: false,
+            pilot_raw: InputState::new(),
+            copilot_raw: InputState::new(),
+            pilot_input: InputState::new(),
+            copilot_input: InputState::new(),
+            hud: true,
+            test: None,
+            error: None,
+            trim_to_save: None,
+        }
+    }
+
+    pub fn configure(&mut self, config: &::config::Config) {
+        self.config = config.control.clone();
+        self.sample_timeout = sample_timeout(&config.timing);
+        self.arming = Arming::new(config.control.arming.button, config.control.arming.hold_time);
+        self.thrusters = build_thrusters(&config.control);
+        self.vehicle = VehicleState::new(&config.staleness);
+        self.reconciler = Reconciler::new(config.reconcile.after);
+        self.vehicle.water_density = config.depth.water.density();
+        self.vehicle.surface_pressure = config.depth.surface_pressure;
+        self.servos = build_servos(&config.control);
+        self.sequences = config.control.sequences.iter().map(|s| s.build()).collect();
+        self.accessories.clear();
+        for light in config.control.lights.iter() {
+            self.accessories.push((light.role, Box::new(light.build())));
+        }
+        if let Some(ref manipulator) = config.control.manipulator {
+            self.accessories.push((manipulator.role, Box::new(manipulator.build())));
+        }
+        self.profile = 0;
+        self.apply_profile();
+    }
+
+    /// Run one step of control on the latest samples, and take in what the vehicle reported.
+    pub fn tick(&mut self, delta: f64) -> Result<()> {
+        let stalled = self.sampled.elapsed() > self.sample_timeout;
+        if stalled != self.stalled {
+            if stalled {
+                warn!("Input stalled, flying with the sticks centred");
+            } else {
+                info!("Input resumed");
+            }
+            self.stalled = stalled;
+        }
+        let empty = InputState::new();
+        self.pilot_raw.follow(if stalled { &empty } else { &self.pilot_sample });
+        match self.copilot_sample {
+            Some(ref sample) => self.copilot_raw.follow(if stalled { &empty } else { sample }),
+            None => self.copilot_raw.advance(),
+        }
+        self.pilot_input = self.shaping.apply(&self.pilot_raw);
+        self.copilot_input = self.shaping.apply(&self.copilot_raw);
+
+        // Without a co-pilot, the pilot does both jobs
+        let pilot_input = &self.pilot_input;
+        let copilot_input = match self.copilot_sample {
+            Some(_) => &self.copilot_input,
+            None => &self.pilot_input,
+        };
+        let input_for = |role| match role {
+            Role::Pilot => Some(pilot_input),
+            Role::CoPilot => Some(copilot_input),
+            Role::Observer => None,
+        };
+
+        let mut commands = vec![];
+        let motors_input = input_for(self.config.roles.motors);
+        // Profiles are cycled by whoever drives the thrusters, and not while a panel is open
+        let profile_button = self.config.profile_button;
+        let next_profile = self.hud &&
+                           motors_input.map_or(false, |input| input.just_pressed(profile_button));
+        if let Some(input) = input_for(self.config.roles.master) {
+            let demand = motors_input.map(Wrench::from_input).unwrap_or_default();
+            self.arming.update(input, &demand, delta);
+        }
+        self.arming.write_commands(&mut commands);
+        self.thrusters.test = if self.arming.is_armed() { self.test } else { None };
+        if let Some(input) = motors_input {
+            // Trim can be set up before arming, but only pushes once armed
+            self.trim.update(input, delta);
+            // Disarmed thrusters see no input, so they wind down and their holds let go
+            let empty = InputState::new();
+            let input = if self.arming.is_armed() { input } else { &empty };
+            self.trim.engage(self.arming.is_armed(), delta);
+            self.thrusters.trim = self.trim.demand();
+            self.thrusters.assist = Wrench::default();
+            if !self.arming.is_armed() {
+                if let Some(ref mut cruise) = self.thrusters.cruise {
+                    cruise.cancel();
+                }
+            }
+            for sequence in self.sequences.iter_mut() {
+                if !self.arming.is_armed() {
+                    sequence.abort();
+                }
+                sequence.update(input, delta);
+                sequence.write_commands(&mut commands);
+                self.thrusters.assist = self.thrusters.assist + sequence.demand;
+            }
+            self.thrusters.observe(&self.vehicle);
+            self.thrusters.update(input, delta);
+            self.thrusters.write_commands(&mut commands);
+        }
+        if let Some(input) = input_for(self.config.roles.servos) {
+            for servo in self.servos.iter_mut() {
+                servo.update(input, delta);
+                servo.write_commands(&mut commands);
+            }
+        }
+        for &mut (role, ref mut control) in self.controls.iter_mut() {
+            if let Some(input) = input_for(role) {
+                control.update(input, delta);
+                control.write_commands(&mut commands);
+            }
+        }
+        // Accessories on the thrusters' gamepad don't see the triggers, which are its heave
+        let shared = motors_input.map(|input| {
+            input.without(&[Button::LeftTrigger2, Button::RightTrigger2])
+        });
+        for &mut (role, ref mut accessory) in self.accessories.iter_mut() {
+            let input = match input_for(role) {
+                Some(input) if motors_input.map_or(false, |motors| ptr::eq(motors, input)) => {
+                    shared.as_ref()
+                }
+                input => input,
+            };
+            if let Some(input) = input {
+                accessory.update(input, delta);
+                accessory.write_commands(&mut commands);
+            }
+        }
+
+        for command in commands {
+            self.send(command).chain_err(|| "Failed to update rov")?;
+        }
+
+        if self.trim.changed {
+            self.keep_trim();
+        }
+
+        let profiles = self.config.profiles.len();
+        if next_profile && profiles > 0 {
+            // The new bindings know nothing of what the old ones switched on, so switch it off
+            let mut commands = vec![];
+            for &mut (_, ref mut control) in self.controls.iter_mut() {
+                control.release(&mut commands);
+            }
+            for command in commands {
+                self.send(command).chain_err(|| "Failed to release the bindings")?;
+            }
+            self.profile = (self.profile + 1) % profiles;
+            self.apply_profile();
+        }
+
+        let responses = self.rov.responses();
+        self.vehicle.apply_responses(&responses);
+        let snapshot = self.vehicle.snapshot();
+        let stale = snapshot.stale();
+        if stale != self.stale {
+            warn!("Vehicle data out of date";
+                  "stale" => stale.join(", "),
+                  "snapshot" => format!("{:?}", snapshot));
+            self.stale = stale;
+        }
+        self.reconciler.check(&self.vehicle, Instant::now());
+        for r in responses {
+            self.arming.observe(&r);
+            for servo in self.servos.iter_mut() {
+                servo.observe(&r);
+            }
+            for &mut (_, ref mut accessory) in self.accessories.iter_mut() {
+                accessory.observe(&r);
+            }
+            match r {
+                RovResponse::NoI2c => info!("No I2C devices found"),
+                RovResponse::I2cFound { address, error_code } => {
+                    info!("I2C device found";
+                         "address" => address,
+                         "error_code" => error_code);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop every thruster and disarm. Arming stays locked until the screen unlocks it.
+    pub fn stop(&mut self, reason: &str) -> Result<()> {
+        let mut commands = vec![];
+        self.thrusters.stop(&mut commands);
+        self.arming.disarm(reason);
+        self.arming.locked = true;
+        self.arming.write_commands(&mut commands);
+        for command in commands {
+            self.send(command).chain_err(|| "Failed to stop the thrusters")?;
+        }
+        Ok(())
+    }
+
+    /// Send a command, noting it so that the vehicle's echo can be checked against it.
+    pub fn send(&mut self, command: RovCommand) -> Result<()> {
+        self.reconciler.commanded(&command, Instant::now());
+        self.rov.send_command(command)
+    }
+
+    /// Switch the pilot's feel, the autopilots and the button bindings to the current profile,
+    /// without touching the connection or anything else.
+    fn apply_profile(&mut self) {
+        let profile = self.config.profiles.get(self.profile);
+        let config = match profile {
+            Some(profile) => {
+                info!("Control profile chosen"; "name" => &profile.name);
+                self.config.with_profile(profile)
+            }
+            None => self.config.clone(),
+        };
+
+        self.shaping = config.shaping.clone();
+        let gain = config.gain.clone();
+        self.thrusters.gain = Gain::new(gain.button, gain.modes, gain.start);
+        for motor in self.thrusters.motors.iter_mut() {
+            let settings = config.motor(motor.id);
+            motor.acceleration = profile.and_then(|profile| profile.acceleration)
+                .unwrap_or(settings.acceleration);
+            motor.deceleration = profile.and_then(|profile| profile.deceleration)
+                .unwrap_or(settings.deceleration);
+        }
+        if let Some(autopilots) = profile.and_then(|profile| profile.autopilots.as_ref()) {
+            use config::Autopilot;
+            self.thrusters.heading_hold.enabled = autopilots.contains(&Autopilot::HeadingHold);
+            self.thrusters.depth_hold.enabled = autopilots.contains(&Autopilot::DepthHold);
+            self.thrusters.stabiliser.set_enabled(autopilots.contains(&Autopilot::Stabiliser));
+        }
+
+        let (roll, pitch) = self.thrusters.authority();
+        let engaged = self.trim.engaged;
+        self.trim = config.trim.build(roll, pitch);
+        self.trim.engaged = engaged;
+
+        self.controls.clear();
+        for binding in config.bindings.iter() {
+            self.controls.push((binding.role, Box::new(binding.build())));
+        }
+    }
+
+    /// Keep the trim with the current profile, or with the base settings if there are none, and
+    /// leave it for the screen to save.
+    fn keep_trim(&mut self) {
+        let offsets = ::config::TrimOffsets::from_offset(&self.trim.offset);
+        let name = match self.config.profiles.get_mut(self.profile) {
+            Some(profile) => {
+                profile.trim = Some(offsets.clone());
+                Some(profile.name.clone())
+            }
+            None => {
+                self.config.trim.offsets = offsets.clone();
+                None
+            }
+        };
+        self.trim_to_save = Some((name, offsets));
+    }
+}
+
+/// How old the samples can get: a few control periods, and the frame the screen might be drawing.
+fn sample_timeout(timing: &::config::Timing) -> Duration {
+    ::util::period(timing.control_rate) * 4 + ::util::period(timing.frame_rate)
+}
+
+/// Tick the flight at `rate` hertz until `running` is cleared or a tick fails. The lock is only
+/// held for the tick itself.
+pub fn spawn(flight: Arc<Mutex<Flight>>, running: Arc<AtomicBool>, rate: f64) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let period = ::util::period(rate);
+        let mut prev_tick = Instant::now();
+        let mut next_tick = prev_tick;
+        while running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+            if next_tick > now {
+                thread::sleep(next_tick - now);
+            }
+            let now = Instant::now();
+            let delta = ::util::seconds(now - prev_tick);
+            prev_tick = now;
+            // After a stall, carry on from now rather than run the missed ticks back to back
+            next_tick += period;
+            if next_tick < now {
+                next_tick = now + period;
+            }
+
+            let mut flight = match flight.lock() {
+                Ok(flight) => flight,
+                Err(_) => break,
+            };
+            if let Err(ref e) = flight.tick(delta) {
+                let error_trace = ::util::get_error_trace(e);
+                error!("Control loop stopped"; "error_trace" => &error_trace);
+                flight.error = Some(error_trace);
+                break;
+            }
+        }
+    })
+}
+
+/// The tilt servo, then the pan servo.
+fn build_servos(config: &::config::Controls) -> Vec<Servo> {
+    vec![config.servo_tilt.build(0, Button::DPadDown, Button::DPadUp),
+         config.servo_pan.build(1, Button::DPadRight, Button::DPadLeft)]
+}
+
+/// Id, position and direction of the motors on the standard frame.
+const LAYOUT: [(u8, [f32; 3], [f32; 3]); 4] = [(0, [-1.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+                                              (1, [-1.0, -1.0, 0.0], [-1.0, 0.0, 0.0]),
+                                              (2, [0.0, -1.0, 1.0], [0.0, 0.0, -1.0]),
+                                              (3, [0.0, 1.0, 1.0], [0.0, 0.0, -1.0])];
+
+/// The motors the thrusters drive: the standard frame's, and any others given a direction.
+pub fn motor_ids(config: &::config::Controls) -> Vec<u8> {
+    let mut ids: Vec<u8> = LAYOUT.iter().map(|&(id, _, _)| id).collect();
+    for motor in config.motors.iter() {
+        if motor.direction.is_some() && !ids.contains(&motor.id) {
+            ids.push(motor.id);
+        }
+    }
+    ids
+}
+
+fn build_thrusters(config: &::config::Controls) -> Thrusters {
+    use control::motor::MotorBuilder;
+    let motors = motor_ids(config)
+        .into_iter()
+        .map(|id| {
+            let motor_config = config.motor(id);
+            let standard = LAYOUT.iter().find(|&&(standard_id, _, _)| standard_id == id);
+            let position = motor_config.position
+                .or(standard.map(|&(_, position, _)| position))
+                .unwrap_or([0.0, 0.0, 0.0]);
+            let direction = motor_config.direction
+                .or(standard.map(|&(_, _, direction)| direction))
+                .unwrap_or([0.0, 0.0, 0.0]);
+            MotorBuilder::new()
+                .id(id)
+                .position(position)
+                .direction(direction)
+                .acceleration(motor_config.acceleration)
+                .deceleration(motor_config.deceleration)
+                .calibration(motor_config.calibration())
+                .build()
+        })
+        .collect();
+    let gain = config.gain.clone();
+    let mut thrusters = Thrusters::new(motors,
+                                       Gain::new(gain.button, gain.modes, gain.start),
+                                       Hold::new(config.heading_hold.button,
+                                                 config.heading_hold.pid.build(),
+                                                 hold::heading_error),
+                                       Hold::new(config.depth_hold.button,
+                                                 config.depth_hold.pid.build(),
+                                                 hold::depth_error),
+                                       Stabiliser::new(config.stabiliser.button,
+                                                       config.stabiliser.roll.build(),
+                                                       config.stabiliser.pitch.build()));
+    if let Some(ref power) = config.power {
+        let budget = power.build();
+        if budget.covers_idle(thrusters.motors.len()) {
+            thrusters.power = Some(budget);
+        } else {
+            warn!("Power budget is below the motors' idle draw; not limiting power";
+                  "budget" => power.budget);
+        }
+    }
+    thrusters.cruise = config.cruise.as_ref().map(|cruise| Cruise::new(cruise.button));
+    let (roll, pitch) = thrusters.authority();
+    if !roll {
+        warn!("No thruster can roll the vehicle, so roll can't be stabilised");
+    }
+    if !pitch {
+        warn!("No thruster can pitch the vehicle, so pitch can't be stabilised");
+    }
+    thrusters
+}
@@ -0,0 +1,39 @@
+use gilrs;
+use super::{Axis, Button, InputState};
+
+const AXES: [(gilrs::Axis, Axis); 6] = [(gilrs::Axis::LeftStickX, Axis::LeftStickX),
+                                        (gilrs::Axis::LeftStickY, Axis::LeftStickY),
+                                        (gilrs::Axis::RightStickX, Axis::RightStickX),
+                                        (gilrs::Axis::RightStickY, Axis::RightStickY),
+                                        (gilrs::Axis::LeftTrigger2, Axis::LeftTrigger2),
+                                        (gilrs::Axis::RightTrigger2, Axis::RightTrigger2)];
+
+const BUTTONS: [(gilrs::Button, Button); 17] = [(gilrs::Button::South, Button::South),
+                                                (gilrs::Button::East, Button::East),
+                                                (gilrs::Button::North, Button::North),
+                                                (gilrs::Button::West, Button::West),
+                                                (gilrs::Button::LeftTrigger, Button::LeftTrigger),
+                                                (gilrs::Button::LeftTrigger2, Button::LeftTrigger2),
+                                                (gilrs::Button::RightTrigger, Button::RightTrigger),
+                                                (gilrs::Button::RightTrigger2, Button::RightTrigger2),
+                                                (gilrs::Button::Select, Button::Select),
+                                                (gilrs::Button::Start, Button::Start),
+                                                (gilrs::Button::Mode, Button::Mode),
+                                                (gilrs::Button::LeftThumb, Button::LeftThumb),
+                                                (gilrs::Button::RightThumb, Button::RightThumb),
+                                                (gilrs::Button::DPadUp, Button::DPadUp),
+                                                (gilrs::Button::DPadDown, Button::DPadDown),
+                                                (gilrs::Button::DPadLeft, Button::DPadLeft),
+                                                (gilrs::Button::DPadRight, Button::DPadRight)];
+
+/// Copy the state of a gilrs gamepad into the input state.
+pub fn sample(gamepad: &gilrs::GamepadState, input: &mut InputState) {
+    for &(gilrs_axis, axis) in AXES.iter() {
+        input.set_value(axis, gamepad.value(gilrs_axis));
+    }
+    for &(gilrs_button, button) in BUTTONS.iter() {
+        if gamepad.is_pressed(gilrs_button) {
+            input.press(button);
+        }
+    }
+}
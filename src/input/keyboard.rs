@@ -0,0 +1,33 @@
+use sdl2::keyboard::{KeyboardState, Scancode};
+use super::{Axis, Button, InputState};
+
+/// Keys that push an axis to one of its ends.
+const AXES: [(Scancode, Axis, f32); 8] = [(Scancode::W, Axis::RightStickY, 1.0),
+                                          (Scancode::S, Axis::RightStickY, -1.0),
+                                          (Scancode::A, Axis::LeftStickX, -1.0),
+                                          (Scancode::D, Axis::LeftStickX, 1.0),
+                                          (Scancode::Q, Axis::RightStickX, -1.0),
+                                          (Scancode::E, Axis::RightStickX, 1.0),
+                                          (Scancode::R, Axis::LeftTrigger2, 1.0),
+                                          (Scancode::F, Axis::RightTrigger2, 1.0)];
+
+const BUTTONS: [(Scancode, Button); 6] = [(Scancode::L, Button::North),
+                                          (Scancode::Return, Button::Start),
+                                          (Scancode::Up, Button::DPadUp),
+                                          (Scancode::Down, Button::DPadDown),
+                                          (Scancode::Left, Button::DPadLeft),
+                                          (Scancode::Right, Button::DPadRight)];
+
+/// Add the held keys to the input state, on top of whatever the gamepad reported.
+pub fn sample(keyboard: &KeyboardState, input: &mut InputState) {
+    for &(scancode, axis, value) in AXES.iter() {
+        if keyboard.is_scancode_pressed(scancode) {
+            input.set_value(axis, value);
+        }
+    }
+    for &(scancode, button) in BUTTONS.iter() {
+        if keyboard.is_scancode_pressed(scancode) {
+            input.press(button);
+        }
+    }
+}
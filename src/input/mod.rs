@@ -0,0 +1,168 @@
+//! Input state that controls read from, independent of the device that produced it.
+
+pub mod gamepad;
+pub mod keyboard;
+pub mod roles;
+pub mod shaping;
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Axis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger2,
+    RightTrigger2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Button {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+/// A sample of named axis values in -1..1 and pressed buttons.
+///
+/// Every sample starts empty; input sources then fill it in. The buttons of the previous sample
+/// are kept around so that controls can react to presses and releases.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputState {
+    axes: HashMap<Axis, f32>,
+    pressed: HashSet<Button>,
+    prev_pressed: HashSet<Button>,
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState::default()
+    }
+
+    /// Start a new sample. The pressed buttons become the previous buttons and everything else is
+    /// cleared.
+    pub fn advance(&mut self) {
+        self.prev_pressed = ::std::mem::replace(&mut self.pressed, HashSet::new());
+        self.axes.clear();
+    }
+
+    /// Start a new sample holding the same buttons and axes as another, e.g. one taken on another
+    /// thread.
+    pub fn follow(&mut self, sample: &InputState) {
+        self.advance();
+        self.pressed = sample.pressed.clone();
+        self.axes = sample.axes.clone();
+    }
+
+    pub fn value(&self, axis: Axis) -> f32 {
+        self.axes.get(&axis).cloned().unwrap_or(0.0)
+    }
+
+    pub fn set_value(&mut self, axis: Axis, value: f32) {
+        self.axes.insert(axis, value.max(-1.0).min(1.0));
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.pressed.insert(button);
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Whether the button went down in this sample.
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button) && !self.prev_pressed.contains(&button)
+    }
+
+    /// Whether the button went up in this sample.
+    pub fn just_released(&self, button: Button) -> bool {
+        !self.pressed.contains(&button) && self.prev_pressed.contains(&button)
+    }
+
+    /// A copy in which the buttons were never pressed.
+    pub fn without(&self, buttons: &[Button]) -> InputState {
+        let mut input = self.clone();
+        for button in buttons.iter() {
+            input.pressed.remove(button);
+            input.prev_pressed.remove(button);
+        }
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_edges() {
+        let mut input = InputState::new();
+        input.press(Button::South);
+        assert!(input.just_pressed(Button::South));
+
+        input.advance();
+        input.press(Button::South);
+        assert!(input.is_pressed(Button::South));
+        assert!(!input.just_pressed(Button::South));
+
+        input.advance();
+        assert!(input.just_released(Button::South));
+    }
+
+    #[test]
+    fn axis_values_are_clamped_and_cleared() {
+        let mut input = InputState::new();
+        input.set_value(Axis::LeftStickX, 2.0);
+        assert_eq!(input.value(Axis::LeftStickX), 1.0);
+        assert_eq!(input.value(Axis::LeftStickY), 0.0);
+
+        input.advance();
+        assert_eq!(input.value(Axis::LeftStickX), 0.0);
+    }
+
+    #[test]
+    fn following_a_sample_keeps_edges_between_ticks() {
+        let mut sample = InputState::new();
+        sample.press(Button::South);
+        sample.set_value(Axis::RightStickY, 0.5);
+
+        let mut input = InputState::new();
+        input.follow(&sample);
+        assert!(input.just_pressed(Button::South));
+        input.follow(&sample);
+        assert!(input.is_pressed(Button::South));
+        assert!(!input.just_pressed(Button::South));
+        assert_eq!(input.value(Axis::RightStickY), 0.5);
+
+        input.follow(&InputState::new());
+        assert!(input.just_released(Button::South));
+    }
+
+    #[test]
+    fn buttons_can_be_left_out() {
+        let mut input = InputState::new();
+        input.press(Button::LeftTrigger2);
+        input.press(Button::LeftTrigger);
+        input.set_value(Axis::LeftTrigger2, 1.0);
+        let without = input.without(&[Button::LeftTrigger2]);
+        assert!(!without.is_pressed(Button::LeftTrigger2));
+        assert!(without.just_pressed(Button::LeftTrigger));
+        assert_eq!(without.value(Axis::LeftTrigger2), 1.0);
+    }
+}
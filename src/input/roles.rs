@@ -0,0 +1,123 @@
+//! Which physical gamepad holds which role.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Role {
+    Pilot,
+    CoPilot,
+    Observer,
+}
+
+impl Role {
+    pub fn next(self) -> Role {
+        match self {
+            Role::Pilot => Role::CoPilot,
+            Role::CoPilot => Role::Observer,
+            Role::Observer => Role::Pilot,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Pilot => "Pilot",
+            Role::CoPilot => "Co-pilot",
+            Role::Observer => "Observer",
+        }
+    }
+}
+
+pub struct Roles {
+    // (gamepad id, role), in the order the gamepads connected
+    gamepads: Vec<(usize, Role)>,
+}
+
+impl Roles {
+    pub fn new() -> Roles {
+        Roles { gamepads: vec![] }
+    }
+
+    /// Make the known gamepads match the connected ones. New gamepads take the first free role.
+    pub fn reconcile<I: Iterator<Item = usize>>(&mut self, connected: I) {
+        let connected: Vec<usize> = connected.collect();
+        self.gamepads.retain(|&(id, _)| connected.contains(&id));
+        for id in connected {
+            if self.role_of(id).is_none() {
+                let role = [Role::Pilot, Role::CoPilot]
+                    .iter()
+                    .cloned()
+                    .find(|&role| self.gamepad_for(role).is_none())
+                    .unwrap_or(Role::Observer);
+                self.gamepads.push((id, role));
+            }
+        }
+    }
+
+    pub fn gamepads(&self) -> &[(usize, Role)] {
+        &self.gamepads
+    }
+
+    pub fn role_of(&self, gamepad: usize) -> Option<Role> {
+        self.gamepads.iter().find(|&&(id, _)| id == gamepad).map(|&(_, role)| role)
+    }
+
+    pub fn gamepad_for(&self, role: Role) -> Option<usize> {
+        self.gamepads.iter().find(|&&(_, r)| r == role).map(|&(id, _)| id)
+    }
+
+    /// Give the gamepad a role. A gamepad that already held the role swaps with it.
+    pub fn assign(&mut self, gamepad: usize, role: Role) {
+        let old_role = match self.role_of(gamepad) {
+            Some(old_role) => old_role,
+            None => return,
+        };
+        if role != Role::Observer {
+            for entry in self.gamepads.iter_mut() {
+                if entry.1 == role {
+                    entry.1 = old_role;
+                }
+            }
+        }
+        for entry in self.gamepads.iter_mut() {
+            if entry.0 == gamepad {
+                entry.1 = role;
+            }
+        }
+    }
+
+    pub fn cycle(&mut self, gamepad: usize) {
+        if let Some(role) = self.role_of(gamepad) {
+            self.assign(gamepad, role.next());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connected_gamepads_fill_free_roles() {
+        let mut roles = Roles::new();
+        roles.reconcile(vec![0, 1, 2].into_iter());
+        assert_eq!(roles.role_of(0), Some(Role::Pilot));
+        assert_eq!(roles.role_of(1), Some(Role::CoPilot));
+        assert_eq!(roles.role_of(2), Some(Role::Observer));
+
+        roles.reconcile(vec![1, 2].into_iter());
+        assert_eq!(roles.gamepad_for(Role::Pilot), None);
+        roles.reconcile(vec![1, 2, 3].into_iter());
+        assert_eq!(roles.role_of(3), Some(Role::Pilot));
+    }
+
+    #[test]
+    fn assigning_a_held_role_swaps() {
+        let mut roles = Roles::new();
+        roles.reconcile(vec![0, 1].into_iter());
+        roles.assign(1, Role::Pilot);
+        assert_eq!(roles.role_of(0), Some(Role::CoPilot));
+        assert_eq!(roles.role_of(1), Some(Role::Pilot));
+
+        roles.cycle(1);
+        assert_eq!(roles.role_of(1), Some(Role::CoPilot));
+        assert_eq!(roles.role_of(0), Some(Role::Pilot));
+    }
+}
@@ -0,0 +1,153 @@
+//! Deadzones and response curves applied to the axes before the controls see them.
+
+use super::{Axis, InputState};
+
+/// How a single axis responds, applied in the order the fields are listed.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AxisShape {
+    /// Values closer to zero than this read as zero. The rest of the range is stretched so the
+    /// output still starts at zero.
+    pub deadzone: f32,
+    /// Blend between a linear (0.0) and cubic (1.0) response. Higher values give finer control
+    /// around the centre.
+    pub expo: f32,
+    pub scale: f32,
+    pub invert: bool,
+}
+
+impl Default for AxisShape {
+    fn default() -> AxisShape {
+        AxisShape {
+            deadzone: 0.0,
+            expo: 0.0,
+            scale: 1.0,
+            invert: false,
+        }
+    }
+}
+
+impl AxisShape {
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = remove_deadzone(value, self.deadzone);
+        let value = (1.0 - self.expo) * value + self.expo * value.powi(3);
+        let value = value * self.scale;
+        if self.invert { -value } else { value }
+    }
+}
+
+/// A stick's axes share a radial deadzone, so that pushing along one axis doesn't snap the other
+/// to zero.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StickShape {
+    pub deadzone: f32,
+    pub x: AxisShape,
+    pub y: AxisShape,
+}
+
+impl Default for StickShape {
+    fn default() -> StickShape {
+        StickShape {
+            deadzone: 0.1,
+            x: AxisShape::default(),
+            y: AxisShape::default(),
+        }
+    }
+}
+
+impl StickShape {
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let magnitude = (x * x + y * y).sqrt();
+        let shaped = remove_deadzone(magnitude.min(1.0), self.deadzone);
+        let (x, y) = if magnitude > 0.0 {
+            (x / magnitude * shaped, y / magnitude * shaped)
+        } else {
+            (0.0, 0.0)
+        };
+        (self.x.apply(x), self.y.apply(y))
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Shaping {
+    pub left_stick: StickShape,
+    pub right_stick: StickShape,
+    pub left_trigger: AxisShape,
+    pub right_trigger: AxisShape,
+}
+
+impl Default for Shaping {
+    fn default() -> Shaping {
+        let trigger = AxisShape { deadzone: 0.05, ..AxisShape::default() };
+        Shaping {
+            left_stick: StickShape::default(),
+            right_stick: StickShape::default(),
+            left_trigger: trigger.clone(),
+            right_trigger: trigger,
+        }
+    }
+}
+
+impl Shaping {
+    /// The raw input with its axes shaped. Buttons are left alone.
+    pub fn apply(&self, raw: &InputState) -> InputState {
+        let mut shaped = raw.clone();
+        let sticks = [(&self.left_stick, Axis::LeftStickX, Axis::LeftStickY),
+                      (&self.right_stick, Axis::RightStickX, Axis::RightStickY)];
+        for &(stick, x_axis, y_axis) in sticks.iter() {
+            let (x, y) = stick.apply(raw.value(x_axis), raw.value(y_axis));
+            shaped.set_value(x_axis, x);
+            shaped.set_value(y_axis, y);
+        }
+        shaped.set_value(Axis::LeftTrigger2,
+                         self.left_trigger.apply(raw.value(Axis::LeftTrigger2)));
+        shaped.set_value(Axis::RightTrigger2,
+                         self.right_trigger.apply(raw.value(Axis::RightTrigger2)));
+        shaped
+    }
+}
+
+fn remove_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadzone_is_removed_and_range_kept() {
+        let shape = AxisShape { deadzone: 0.2, ..AxisShape::default() };
+        assert_eq!(shape.apply(0.1), 0.0);
+        assert_eq!(shape.apply(-1.0), -1.0);
+        assert!((shape.apply(0.6) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn expo_scale_and_invert() {
+        let shape = AxisShape {
+            deadzone: 0.0,
+            expo: 1.0,
+            scale: 0.5,
+            invert: true,
+        };
+        assert!((shape.apply(0.5) + 0.0625).abs() < 1e-6);
+    }
+
+    #[test]
+    fn stick_deadzone_is_radial() {
+        let stick = StickShape { deadzone: 0.2, ..StickShape::default() };
+        assert_eq!(stick.apply(0.1, 0.1), (0.0, 0.0));
+
+        // Diagonal drift is caught by the radius, but a push along one axis keeps the other
+        let (x, y) = stick.apply(1.0, 0.15);
+        assert!(x > 0.9);
+        assert!(y > 0.1);
+    }
+}
@@ -28,10 +28,13 @@ extern crate unicode_normalization;
 
 pub mod errors;
 mod rov;
-mod mock;
+mod vehicle;
+mod reconcile;
+mod flight;
 mod util;
 mod screen;
 mod control;
+mod input;
 mod config;
 
 use errors::*;
@@ -98,8 +101,8 @@ fn run() -> Result<()> {
 
     let rfont = load_font().chain_err(|| "Failed to load r font")?;
 
+    // No vsync, as presenting a frame would then hold up sampling the gamepads
     let canvas = window.into_canvas()
-        .present_vsync()
         .accelerated()
         .build().chain_err(|| "Failed to accelerate canvas")?;
 
@@ -145,23 +148,48 @@ fn run() -> Result<()> {
         }
         None => Box::new(screen::port_select::PortSelect::new()),
     };
-
-    let mut prev_time = ::std::time::Instant::now();
+    screen.init(&mut engine).chain_err(|| "Failed to initialize screen")?;
+
+    use std::time::Instant;
+    let control_period = util::period(engine.config.timing.control_rate);
+    let frame_period = util::period(engine.config.timing.frame_rate);
+    let mut prev_tick = Instant::now();
+    let mut next_tick = prev_tick;
+    let mut prev_frame = prev_tick;
     loop {
-        let elapsed = prev_time.elapsed();
-        let delta = (elapsed.as_secs() as f64) + (elapsed.subsec_nanos() as f64 / 1_000_000_000.0);
-        prev_time = ::std::time::Instant::now();
+        // Input is sampled on a fixed schedule, with frames drawn in between when they are due.
+        // The ROV is flown from a thread of its own, so neither holds up its commands.
+        let now = Instant::now();
+        if next_tick > now {
+            ::std::thread::sleep(next_tick - now);
+        }
+        let now = Instant::now();
+        let delta = util::seconds(now - prev_tick);
+        prev_tick = now;
+        // After a stall, carry on from now rather than run the missed ticks back to back
+        next_tick += control_period;
+        if next_tick < now {
+            next_tick = now + control_period;
+        }
 
         let trans = screen.update(&mut engine, delta).chain_err(|| "Failed to update screen")?;
 
-        use sdl2::pixels::Color;
-        engine.canvas.set_draw_color(Color::RGB(0, 0, 0));
-        engine.canvas.set_blend_mode(BlendMode::Blend);
-        engine.canvas.clear();
-        engine.canvas.set_draw_color(Color::RGB(255, 255, 255));
-        screen.render(&mut engine, delta).chain_err(|| "Failed to render screen")?;
-        engine.render_text();
-        engine.canvas.present();
+        use sdl2::sys::video::SDL_WindowFlags::{SDL_WINDOW_HIDDEN, SDL_WINDOW_MINIMIZED};
+        let hidden = SDL_WINDOW_HIDDEN as u32 | SDL_WINDOW_MINIMIZED as u32;
+        let visible = engine.canvas.window().window_flags() & hidden == 0;
+        if visible && prev_frame.elapsed() >= frame_period {
+            let delta = util::seconds(prev_frame.elapsed());
+            prev_frame = Instant::now();
+
+            use sdl2::pixels::Color;
+            engine.canvas.set_draw_color(Color::RGB(0, 0, 0));
+            engine.canvas.set_blend_mode(BlendMode::Blend);
+            engine.canvas.clear();
+            engine.canvas.set_draw_color(Color::RGB(255, 255, 255));
+            screen.render(&mut engine, delta).chain_err(|| "Failed to render screen")?;
+            engine.render_text();
+            engine.canvas.present();
+        }
 
         let current_screen = match trans {
             screen::Trans::Quit => break,
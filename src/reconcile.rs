@@ -0,0 +1,260 @@
+/// Compares what was sent to the ROV with what it reports, to catch parts that don't do as
+/// they're told.
+
+use rov::RovCommand;
+use std::collections::BTreeMap;
+use std::time::Instant;
+use vehicle::{Reading, VehicleState};
+
+/// Something the ROV echoes the state of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Part {
+    Master,
+    LightRelay,
+    Motor(u8),
+    Servo(u8),
+    Light(u8),
+}
+
+impl Part {
+    fn name(&self) -> String {
+        match *self {
+            Part::Master => "Master power".into(),
+            Part::LightRelay => "Light relay".into(),
+            Part::Motor(id) => format!("Motor {}", id),
+            Part::Servo(id) => format!("Servo {}", id),
+            Part::Light(id) => format!("Light {}", id),
+        }
+    }
+
+    fn describe(&self, value: i32) -> String {
+        match *self {
+            Part::Master | Part::LightRelay if value == 0 => "off".into(),
+            Part::Master | Part::LightRelay => "on".into(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mismatch {
+    pub part: Part,
+    pub desired: i32,
+    /// None if the ROV hasn't answered since the part was last sent a command
+    pub reported: Option<i32>,
+}
+
+impl Mismatch {
+    pub fn describe(&self) -> String {
+        match self.reported {
+            None => format!("{} not answering", self.part.name()),
+            Some(reported) => {
+                format!("{} reports {}, sent {}",
+                        self.part.name(),
+                        self.part.describe(reported),
+                        self.part.describe(self.desired))
+            }
+        }
+    }
+}
+
+/// Keeps the last value sent to each part, and flags the parts whose echo hasn't matched it for
+/// longer than `after` seconds.
+pub struct Reconciler {
+    // info
+    after: f64,
+    // state
+    /// The value each part was last sent, and when
+    desired: BTreeMap<Part, (i32, Instant)>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Reconciler {
+    pub fn new(after: f64) -> Self {
+        Reconciler {
+            after: after,
+            desired: BTreeMap::new(),
+            mismatches: vec![],
+        }
+    }
+
+    /// Note a command as it is sent.
+    pub fn commanded(&mut self, command: &RovCommand, now: Instant) {
+        // Switching master power resets everything else on the ROV, so expect the reset values
+        match *command {
+            RovCommand::MasterOn | RovCommand::MasterOff => self.reset_parts(now),
+            _ => {}
+        }
+        let (part, value) = match *command {
+            RovCommand::ControlMotor { id, throttle } => (Part::Motor(id), throttle as i32),
+            RovCommand::ControlServo { id, microseconds } => (Part::Servo(id), microseconds as i32),
+            RovCommand::SetLight { id, level } => (Part::Light(id), level as i32),
+            RovCommand::LightsOn => (Part::LightRelay, 1),
+            RovCommand::LightsOff => (Part::LightRelay, 0),
+            RovCommand::MasterOn => (Part::Master, 1),
+            RovCommand::MasterOff => (Part::Master, 0),
+        };
+        self.desired.insert(part, (value, now));
+    }
+
+    /// Expect what the firmware's `master_on` and `master_off` leave behind: motors stopped and
+    /// the lights off. The servos are centred too, but are sent back to where they were, so they
+    /// are only given time to get there.
+    fn reset_parts(&mut self, now: Instant) {
+        for (&part, desired) in self.desired.iter_mut() {
+            match part {
+                Part::Master => continue,
+                Part::LightRelay | Part::Motor(_) | Part::Light(_) => desired.0 = 0,
+                Part::Servo(_) => {}
+            }
+            desired.1 = now;
+        }
+    }
+
+    /// Compare the last values sent with what the vehicle has reported since.
+    pub fn check(&mut self, vehicle: &VehicleState, now: Instant) {
+        // With master power off the ROV ignores everything else it is sent
+        let master_off = self.desired.get(&Part::Master).map_or(false, |&(value, _)| value == 0);
+        let mut mismatches = vec![];
+        for (&part, &(desired, sent)) in self.desired.iter() {
+            if master_off && part != Part::Master {
+                continue;
+            }
+            let waited = now.duration_since(sent);
+            let waited = waited.as_secs() as f64 + waited.subsec_nanos() as f64 / 1_000_000_000.0;
+            if waited <= self.after {
+                continue;
+            }
+            let (reported, received) = match reported(vehicle, part) {
+                Some(reported) => reported,
+                None => continue,
+            };
+            let answered = received.map_or(false, |received| received >= sent);
+            if !answered || reported != desired {
+                mismatches.push(Mismatch {
+                    part: part,
+                    desired: desired,
+                    reported: if answered { Some(reported) } else { None },
+                });
+            }
+        }
+
+        for mismatch in mismatches.iter() {
+            if !self.mismatches.iter().any(|known| known.part == mismatch.part) {
+                warn!("ROV state differs from what was sent"; "mismatch" => mismatch.describe());
+            }
+        }
+        self.mismatches = mismatches;
+    }
+
+    pub fn write_status(&self, output: &mut Vec<String>) {
+        for mismatch in self.mismatches.iter() {
+            output.push(mismatch.describe());
+        }
+    }
+}
+
+/// The value the vehicle last reported for a part and when, if it reports that part at all.
+fn reported(vehicle: &VehicleState, part: Part) -> Option<(i32, Option<Instant>)> {
+    fn echo<T: Copy + Into<i32>>(reading: &Reading<T>) -> (i32, Option<Instant>) {
+        (reading.value.into(), reading.received)
+    }
+    match part {
+        Part::Master => Some(echo(&vehicle.master)),
+        Part::LightRelay => Some(echo(&vehicle.light_relay)),
+        Part::Motor(id) => vehicle.motors.get(id as usize).map(echo),
+        Part::Servo(id) => vehicle.servos.get(id as usize).map(echo),
+        Part::Light(id) => vehicle.lights.get(id as usize).map(echo),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rov::RovResponse;
+    use std::time::Duration;
+
+    #[test]
+    fn silent_and_wrong_parts_are_flagged_after_a_while() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        let mut reconciler = Reconciler::new(1.0);
+        let start = Instant::now();
+        reconciler.commanded(&RovCommand::MasterOn, start);
+        reconciler.commanded(&RovCommand::ControlMotor { id: 2, throttle: 800 }, start);
+        reconciler.commanded(&RovCommand::LightsOn, start);
+        vehicle.apply_response(&RovResponse::MasterOn, start);
+        vehicle.apply_response(&RovResponse::LightsOff, start);
+
+        reconciler.check(&vehicle, start + Duration::from_millis(500));
+        assert!(reconciler.mismatches.is_empty());
+
+        reconciler.check(&vehicle, start + Duration::from_secs(2));
+        assert_eq!(reconciler.mismatches,
+                   vec![Mismatch {
+                            part: Part::LightRelay,
+                            desired: 1,
+                            reported: Some(0),
+                        },
+                        Mismatch {
+                            part: Part::Motor(2),
+                            desired: 800,
+                            reported: None,
+                        }]);
+        assert_eq!(reconciler.mismatches[0].describe(), "Light relay reports off, sent on");
+
+        let later = start + Duration::from_secs(3);
+        vehicle.apply_response(&RovResponse::Motor { id: 2, throttle: 800 }, later);
+        reconciler.check(&vehicle, later);
+        assert_eq!(reconciler.mismatches.len(), 1);
+    }
+
+    #[test]
+    fn nothing_but_master_is_checked_while_it_is_off() {
+        let vehicle = VehicleState::new(&::config::Staleness::default());
+        let mut reconciler = Reconciler::new(1.0);
+        let start = Instant::now();
+        reconciler.commanded(&RovCommand::ControlServo { id: 0, microseconds: 1800 }, start);
+        reconciler.commanded(&RovCommand::MasterOff, start);
+
+        reconciler.check(&vehicle, start + Duration::from_secs(2));
+        assert_eq!(reconciler.mismatches.len(), 1);
+        assert_eq!(reconciler.mismatches[0].part, Part::Master);
+    }
+
+    #[test]
+    fn master_power_cycle_expects_the_reset_values() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        let mut reconciler = Reconciler::new(1.0);
+        let start = Instant::now();
+        reconciler.commanded(&RovCommand::MasterOn, start);
+        reconciler.commanded(&RovCommand::ControlServo { id: 0, microseconds: 1800 }, start);
+        reconciler.commanded(&RovCommand::SetLight { id: 1, level: 200 }, start);
+        vehicle.apply_response(&RovResponse::MasterOn, start);
+        vehicle.apply_response(&RovResponse::Servo { id: 0, microseconds: 1800 }, start);
+        vehicle.apply_response(&RovResponse::Light { id: 1, level: 200 }, start);
+
+        let off = start + Duration::from_secs(2);
+        reconciler.commanded(&RovCommand::MasterOff, off);
+        vehicle.apply_response(&RovResponse::MasterOff, off);
+        vehicle.apply_response(&RovResponse::Servo { id: 0, microseconds: 1500 }, off);
+        vehicle.apply_response(&RovResponse::Light { id: 1, level: 0 }, off);
+
+        let on = start + Duration::from_secs(4);
+        reconciler.commanded(&RovCommand::MasterOn, on);
+        vehicle.apply_response(&RovResponse::MasterOn, on);
+        vehicle.apply_response(&RovResponse::LightsOff, on);
+        vehicle.apply_response(&RovResponse::Servo { id: 0, microseconds: 1500 }, on);
+        vehicle.apply_response(&RovResponse::Light { id: 1, level: 0 }, on);
+
+        // The servo is expected back where it was sent
+        reconciler.check(&vehicle, on + Duration::from_secs(2));
+        assert_eq!(reconciler.mismatches.len(), 1);
+        assert_eq!(reconciler.mismatches[0].part, Part::Servo(0));
+
+        let resent = on + Duration::from_secs(3);
+        reconciler.commanded(&RovCommand::ControlServo { id: 0, microseconds: 1800 }, resent);
+        vehicle.apply_response(&RovResponse::Servo { id: 0, microseconds: 1800 }, resent);
+        reconciler.check(&vehicle, on + Duration::from_secs(5));
+        assert_eq!(reconciler.mismatches, vec![]);
+    }
+}
@@ -9,15 +9,22 @@ use std::sync::mpsc::{self, Sender, Receiver};
 const COMMAND_CONTROL_MOTOR: u8 = 0x10;
 const COMMAND_LIGHTS_ON: u8 = 0x31;
 const COMMAND_LIGHTS_OFF: u8 = 0x30;
+const COMMAND_SET_LIGHT: u8 = 0x32;
 const COMMAND_MASTER_ON: u8 = 0x40;
 const COMMAND_MASTER_OFF: u8 = 0x43;
 const COMMAND_CONTROL_SERVO: u8 = 0x66;
 
-#[derive(Clone, Debug)]
+/// In the config file a command is a table naming the variant, e.g.
+/// `{ command = "ControlServo", id = 0, microseconds = 1500 }`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "command")]
 pub enum RovCommand {
     ControlMotor { id: u8, throttle: i16 },
+    /// Switches the relay, which firmware from before dimmable lights also understands.
     LightsOn,
     LightsOff,
+    /// Sets a dimmable channel's brightness, from 0 for off to 255 for full.
+    SetLight { id: u8, level: u8 },
     MasterOn,
     MasterOff,
     ControlServo { id: u8, microseconds: i16 },
@@ -34,6 +41,7 @@ impl RovCommand {
             }
             RovCommand::LightsOn => vec![COMMAND_LIGHTS_ON],
             RovCommand::LightsOff => vec![COMMAND_LIGHTS_OFF],
+            RovCommand::SetLight { id, level } => vec![COMMAND_SET_LIGHT, id, level],
             RovCommand::MasterOn => vec![COMMAND_MASTER_ON],
             RovCommand::MasterOff => vec![COMMAND_MASTER_OFF],
             RovCommand::ControlServo { id, microseconds } => {
@@ -49,8 +57,11 @@ impl RovCommand {
 const RESPONSE_MOTOR: u8 = 0x10;
 const RESPONSE_COMPASS_ORIENTATION: u8 = 0x20;
 const RESPONSE_COMPASS_DISABLED: u8 = 0x21;
+const RESPONSE_PRESSURE: u8 = 0x50;
+const RESPONSE_PRESSURE_DISABLED: u8 = 0x51;
 const RESPONSE_LIGHTS_ON: u8 = 0x31;
 const RESPONSE_LIGHTS_OFF: u8 = 0x30;
+const RESPONSE_LIGHT: u8 = 0x32;
 const RESPONSE_MASTER_ON: u8 = 0x40;
 const RESPONSE_MASTER_OFF: u8 = 0x43;
 const RESPONSE_SERVO: u8 = 0x66;
@@ -62,8 +73,11 @@ pub enum RovResponse {
     Motor { id: u8, throttle: i16 },
     CompassOrientation { x: i16, y: i16, z: i16 },
     CompassDisabled,
+    Pressure { pascals: u32 },
+    PressureDisabled,
     LightsOn,
     LightsOff,
+    Light { id: u8, level: u8 },
     MasterOn,
     MasterOff,
     Servo { id: u8, microseconds: i16 },
@@ -85,8 +99,11 @@ impl RovResponse {
             RESPONSE_MOTOR => Some(3),
             RESPONSE_COMPASS_ORIENTATION => Some(6),
             RESPONSE_COMPASS_DISABLED => Some(0),
+            RESPONSE_PRESSURE => Some(4),
+            RESPONSE_PRESSURE_DISABLED => Some(0),
             RESPONSE_LIGHTS_ON => Some(0),
             RESPONSE_LIGHTS_OFF => Some(0),
+            RESPONSE_LIGHT => Some(2),
             RESPONSE_MASTER_ON => Some(0),
             RESPONSE_MASTER_OFF => Some(0),
             RESPONSE_SERVO => Some(3),
@@ -125,8 +142,21 @@ impl RovResponse {
             }
 
             RESPONSE_COMPASS_DISABLED => RovResponse::CompassDisabled,
+            RESPONSE_PRESSURE => {
+                RovResponse::Pressure {
+                    pascals: ((buffer[1] as u32) << 24) | ((buffer[2] as u32) << 16) |
+                             ((buffer[3] as u32) << 8) | (buffer[4] as u32),
+                }
+            }
+            RESPONSE_PRESSURE_DISABLED => RovResponse::PressureDisabled,
             RESPONSE_LIGHTS_ON => RovResponse::LightsOn,
             RESPONSE_LIGHTS_OFF => RovResponse::LightsOff,
+            RESPONSE_LIGHT => {
+                RovResponse::Light {
+                    id: buffer[1],
+                    level: buffer[2],
+                }
+            }
             RESPONSE_MASTER_ON => RovResponse::MasterOn,
             RESPONSE_MASTER_OFF => RovResponse::MasterOff,
             RESPONSE_SERVO => {
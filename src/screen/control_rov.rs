@@ -1,27 +1,39 @@
-
 use rov::Rov;
-use mock::MockRov;
-use screen::{Engine, Screen, Trans};
-use time::{PreciseTime, Duration};
+use vehicle::VehicleState;
+use flight::{self, Flight};
 use control::Control;
+use screen::{Engine, Screen, Trans};
+use input::InputState;
+use input::roles::{Role, Roles};
+use screen::panel::Panel;
 use ::errors::*;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Instant;
 
+/// Shows the state of the ROV and hands the gamepads to the flight, which runs on its own thread.
 pub struct RovControl {
-    controls: Vec<Box<Control>>,
-    last_write_time: PreciseTime,
-    rov: Rov,
-    mock_rov: MockRov,
+    flight: Arc<Mutex<Flight>>,
+    /// Cleared to stop the control thread
+    running: Arc<AtomicBool>,
+    control_thread: Option<JoinHandle<()>>,
     renderables: Vec<Box<Renderable>>,
-    prev_gamepad_state: ::gilrs::GamepadState,
+    roles: Roles,
+    /// Gamepads that disconnected while in control, with their roles. Arming stays locked until
+    /// they are back in them.
+    lost_gamepads: Vec<(usize, Role)>,
+    panel: Panel,
+    /// Shown over every panel until dealt with
+    alert: Option<String>,
 }
 
 impl RovControl {
     pub fn new(rov: Rov) -> RovControl {
         RovControl {
-            controls: vec![],
-            last_write_time: PreciseTime::now(),
-            rov: rov,
-            mock_rov: MockRov::new(),
+            flight: Arc::new(Mutex::new(Flight::new(rov))),
+            running: Arc::new(AtomicBool::new(false)),
+            control_thread: None,
             renderables: vec![Box::new(MotorRenderable::new(0, [30.0, 50.0], [230.0, 50.0])),
                               Box::new(MotorRenderable::new(1, [30.0, 100.0], [230.0, 100.0])),
                               Box::new(MotorRenderable::new(2, [75.0, 200.0], [75.0, 400.0])),
@@ -33,58 +45,73 @@ impl RovControl {
                               Box::new(DualServoRenderable::new([1, 0],
                                                                 [250.0, 20.0],
                                                                 [360.0, 130.0])),
-                              Box::new(CompassRenderable::new([400, 240]))],
-            prev_gamepad_state: ::gilrs::GamepadState::default(),
+                              Box::new(CompassRenderable::new([400, 240])),
+                              Box::new(DepthRenderable::new([620, 240]))],
+            roles: Roles::new(),
+            lost_gamepads: vec![],
+            panel: Panel::Hud,
+            alert: None,
         }
     }
 }
 
+/// The flight, or why the control thread stopped.
+fn lock<'a>(flight: &'a Mutex<Flight>) -> Result<MutexGuard<'a, Flight>> {
+    let flight = flight.lock().map_err(|_| Error::from("The control thread panicked"))?;
+    if let Some(ref error) = flight.error {
+        bail!("The control thread stopped: {}", error);
+    }
+    Ok(flight)
+}
+
 impl Screen for RovControl {
     fn init(&mut self, engine: &mut Engine) -> Result<()> {
-        use gilrs;
-        self.controls.clear();
-        self.controls.push(Box::new(::control::motor::MotorBuilder::new()
-            .id(0)
-            .position([-1.0, 1.0, 0.0])
-            .direction([-1.0, 0.0, 0.0])
-            .build()));
-        self.controls.push(Box::new(::control::motor::MotorBuilder::new()
-            .id(1)
-            .position([-1.0, -1.0, 0.0])
-            .direction([-1.0, 0.0, 0.0])
-            .build()));
-        self.controls.push(Box::new(::control::motor::MotorBuilder::new()
-            .id(2)
-            .position([0.0, -1.0, 1.0])
-            .direction([0.0, 0.0, -1.0])
-            .build()));
-        self.controls.push(Box::new(::control::motor::MotorBuilder::new()
-            .id(3)
-            .position([0.0, 1.0, 1.0])
-            .direction([0.0, 0.0, -1.0])
-            .build()));
-        self.controls.push(Box::new(::control::lights::Lights::new(gilrs::Button::North)));
-        self.controls.push(Box::new(::control::master::MasterPower::new(gilrs::Button::Start)));
-
-        self.controls.push(Box::new(::control::servo::Servo::new(0,
-                                                                 gilrs::Button::DPadDown,
-                                                                 gilrs::Button::DPadUp,
-                                                                 engine.config
-                                                                     .control
-                                                                     .servo_tilt
-                                                                     .speed)));
-        self.controls.push(Box::new(::control::servo::Servo::new(1,
-                                                                 gilrs::Button::DPadRight,
-                                                                 gilrs::Button::DPadLeft,
-                                                                 engine.config
-                                                                     .control
-                                                                     .servo_pan
-                                                                     .speed)));
+        lock(&self.flight)?.configure(&engine.config);
+        for (index, light) in engine.config.control.lights.iter().enumerate() {
+            let x = 250 + 40 * index as i32;
+            self.renderables.push(Box::new(LightRenderable::new(light.id, [x, 330])));
+        }
+        if let Some(ref manipulator) = engine.config.control.manipulator {
+            self.renderables.push(Box::new(ManipulatorRenderable::new(manipulator, [620, 390])));
+        }
+        self.running.store(true, Ordering::SeqCst);
+        self.control_thread = Some(flight::spawn(self.flight.clone(),
+                                                 self.running.clone(),
+                                                 engine.config.timing.control_rate));
         Ok(())
     }
 
-    fn update(&mut self, engine: &mut Engine, delta: f64) -> Result<Trans> {
-        for (_, _controller_event) in engine.controllers.poll_events() {}
+    /// Sample the gamepads and keyboard for the flight, and deal with gamepads coming and going.
+    fn update(&mut self, engine: &mut Engine, _delta: f64) -> Result<Trans> {
+        let shared = self.flight.clone();
+        let mut flight = lock(&shared)?;
+        let mut lost = vec![];
+        for (id, event) in engine.controllers.poll_events() {
+            use gilrs::Event::{ButtonPressed, Disconnected};
+            use gilrs::Button::Select;
+
+            match (&mut self.panel, event) {
+                (_, Disconnected) => lost.push(id),
+                (&mut Panel::Roles(_), ButtonPressed(Select, _)) => self.roles.cycle(id),
+                (&mut Panel::Tuning(ref mut panel), ButtonPressed(button, _)) => {
+                    panel.button_pressed(button, &mut flight.thrusters)
+                }
+                _ => {}
+            }
+        }
+        for id in lost {
+            self.gamepad_lost(id, &mut flight, engine)?;
+        }
+        self.roles.reconcile(engine.controllers.gamepads().map(|(id, _)| id));
+        // Another gamepad taking the role isn't enough; the one that was lost has to come back
+        let roles = &self.roles;
+        if flight.arming.locked &&
+           self.lost_gamepads.iter().all(|&(id, role)| roles.role_of(id) == Some(role)) {
+            info!("Gamepad reconnected; arming allowed");
+            flight.arming.locked = false;
+            self.lost_gamepads.clear();
+            self.alert = None;
+        }
 
         for event in engine.event_pump.poll_iter() {
             use sdl2::event::Event;
@@ -93,45 +120,80 @@ impl Screen for RovControl {
             match event {
                 Event::Quit { .. } |
                 Event::KeyUp { keycode: Some(Keycode::Escape), .. } => return Ok(Trans::Quit),
+                Event::KeyDown { keycode: Some(Keycode::Tab), .. } => {
+                    self.panel = self.panel.next()
+                }
+                Event::KeyDown { keycode: Some(keycode), .. } => {
+                    match self.panel {
+                        Panel::Roles(ref mut panel) => panel.key_down(keycode, &mut self.roles),
+                        Panel::Inputs(ref mut panel) => panel.key_down(keycode),
+                        Panel::Tuning(ref mut panel) => {
+                            panel.key_down(keycode, &mut flight.thrusters)
+                        }
+                        Panel::Motors(ref mut panel) => {
+                            panel.key_down(keycode, &mut flight.thrusters)
+                        }
+                        Panel::Servos(ref mut panel) => {
+                            if let Some(command) =
+                                panel.key_down(keycode, &mut flight.servos) {
+                                flight.send(command).chain_err(|| "Failed to move servo")?;
+                            }
+                        }
+                        Panel::Hud => {}
+                    }
+                }
                 _ => (),
             }
         }
 
-        let now = PreciseTime::now();
-        if self.last_write_time.to(now) >= Duration::milliseconds(5) {
-            if let Some((_id, gamepad)) = engine.controllers.gamepads().next() {
-                let gamepad_state = gamepad.state();
-                if &self.prev_gamepad_state != gamepad_state {
-                    self.prev_gamepad_state = gamepad_state.clone();
-                }
-                let mut commands = vec![];
-                for control in self.controls.iter_mut() {
-                    control.update(&gamepad_state, delta);
-                    control.write_commands(&mut commands);
-                }
-
-                for command in commands.iter() {
-                    self.rov.send_command(command.clone()).chain_err(|| "Failed to update rov")?;
-                }
-
-                self.last_write_time = now;
+        let mut pilot = InputState::new();
+        if let Some(gamepad) = self.roles.gamepad_for(Role::Pilot)
+            .and_then(|id| engine.controllers.connected_gamepad(id)) {
+            ::input::gamepad::sample(gamepad.state(), &mut pilot);
+        }
+        // The keyboard is left to the panels while one is open
+        if let Panel::Hud = self.panel {
+            ::input::keyboard::sample(&engine.event_pump.keyboard_state(), &mut pilot);
+        }
+        flight.pilot_sample = pilot;
+        flight.copilot_sample = self.roles.gamepad_for(Role::CoPilot).map(|id| {
+            let mut copilot = InputState::new();
+            if let Some(gamepad) = engine.controllers.connected_gamepad(id) {
+                ::input::gamepad::sample(gamepad.state(), &mut copilot);
             }
+            copilot
+        });
+        flight.sampled = Instant::now();
+        flight.hud = match self.panel {
+            Panel::Hud => true,
+            _ => false,
+        };
+        flight.test = match self.panel {
+            Panel::Motors(ref panel) => {
+                use sdl2::keyboard::Scancode;
+                let keyboard = engine.event_pump.keyboard_state();
+                panel.test(keyboard.is_scancode_pressed(Scancode::Space))
+            }
+            _ => None,
+        };
+        if let Panel::Tuning(ref mut panel) = self.panel {
+            panel.record(&flight.thrusters);
         }
 
-        let responses = self.rov.responses();
-        self.mock_rov.apply_responses(&responses);
-        for r in responses {
-            use rov::RovResponse;
-            match r {
-                RovResponse::NoI2c => info!("No I2C devices found"),
-                RovResponse::I2cFound { address, error_code } => {
-                    info!("I2C device found";
-                         "address" => address,
-                         "error_code" => error_code);
-                }
-                _ => {}
+        // Saved to config.toml after the flight is let go, so the control thread doesn't wait
+        let trim = flight.trim_to_save.take();
+        drop(flight);
+        self.panel.save(&mut engine.config);
+        if let Some((name, offsets)) = trim {
+            let result = ::util::save_trim_to_file("config.toml",
+                                                   name.as_ref().map(|name| name.as_str()),
+                                                   &offsets);
+            if let Err(ref e) = result {
+                let error_trace = ::util::get_error_trace(e);
+                warn!("Failed to save trim"; "error_trace" => error_trace);
             }
         }
+
         use std::io::{stdout, Write};
         let _ = stdout().flush();
 
@@ -139,33 +201,152 @@ impl Screen for RovControl {
     }
 
     fn render(&mut self, engine: &mut Engine, delta: f64) -> Result<()> {
+        // Held while the frame's drawing is queued up; text is rasterised and the frame presented
+        // after it is let go
+        let shared = self.flight.clone();
+        let flight = lock(&shared)?;
+        if let Some(ref alert) = self.alert {
+            engine.canvas.draw_rect((20, 540, 760, 50).into()).unwrap();
+            engine.queue_text(30.0, 580.0, Scale::uniform(40.0), alert);
+        }
+
+        match self.panel {
+            Panel::Roles(ref panel) => {
+                panel.render(&self.roles, engine);
+                return Ok(());
+            }
+            Panel::Inputs(ref panel) => {
+                match panel.role {
+                    Role::CoPilot => {
+                        panel.render(&flight.copilot_raw, &flight.copilot_input, engine)
+                    }
+                    _ => panel.render(&flight.pilot_raw, &flight.pilot_input, engine),
+                }
+                return Ok(());
+            }
+            Panel::Tuning(ref panel) => {
+                panel.render(&flight.thrusters, engine);
+                return Ok(());
+            }
+            Panel::Servos(ref panel) => {
+                panel.render(&flight.servos, engine);
+                return Ok(());
+            }
+            Panel::Motors(ref panel) => {
+                panel.render(&flight.thrusters, flight.arming.is_armed(), engine);
+                return Ok(());
+            }
+            Panel::Hud => {}
+        }
+
         let rect = (30, 450, 70, 70).into();
-        if self.mock_rov.robot_is_on {
+        let show_text = show_stale(engine, flight.vehicle.master.is_stale());
+        if flight.vehicle.master.value {
             engine.canvas.fill_rect(rect).unwrap()
         } else {
             engine.canvas.draw_rect(rect).unwrap()
         }
         use rusttype::Scale;
-        engine.queue_text(30.0, 510.0, Scale::uniform(64.0), "Master");
+        if show_text {
+            engine.queue_text(30.0, 510.0, Scale::uniform(64.0), "Master");
+        }
 
         let rect = (120, 450, 50, 50).into();
-        if self.mock_rov.light_relay {
+        let show_text = show_stale(engine, flight.vehicle.light_relay.is_stale());
+        if flight.vehicle.light_relay.value {
             engine.canvas.fill_rect(rect).unwrap()
         } else {
             engine.canvas.draw_rect(rect).unwrap()
         }
-        engine.queue_text(120.0, 500.0, Scale::uniform(32.0), "Lights");
+        if show_text {
+            engine.queue_text(120.0, 500.0, Scale::uniform(32.0), "Lights");
+        }
+        show_stale(engine, false);
 
         for renderable in self.renderables.iter() {
-            renderable.render(&self.mock_rov, engine);
+            renderable.render(&flight.vehicle, engine);
+            show_stale(engine, false);
+        }
+
+        if let Some(mode) = flight.thrusters.gain.mode() {
+            engine.queue_text(200.0, 450.0, Scale::uniform(48.0), &mode.name);
+        }
+        let profile = engine.config.control.profiles.get(flight.profile).map(|p| p.name.clone());
+        if let Some(name) = profile {
+            engine.queue_text(200.0, 500.0, Scale::uniform(32.0), &name);
         }
 
+        let mut status = vec![];
+        flight.arming.write_status(&mut status);
+        flight.thrusters.write_status(&mut status);
+        flight.trim.write_status(&mut status);
+        flight.reconciler.write_status(&mut status);
+        for sequence in flight.sequences.iter() {
+            sequence.write_status(&mut status);
+        }
+        for &(_, ref control) in flight.controls.iter().chain(flight.accessories.iter()) {
+            control.write_status(&mut status);
+        }
+        let mut y = 20.0;
+        for line in status.iter() {
+            engine.queue_text(400.0, y, Scale::uniform(24.0), line);
+            y += 24.0;
+        }
+
+        Ok(())
+    }
+}
+
+impl RovControl {
+    /// Stop the vehicle if the gamepad drove the thrusters or held the arming button. Arming stays
+    /// locked until that gamepad reconnects and takes its role again.
+    fn gamepad_lost(&mut self, id: usize, flight: &mut Flight, engine: &Engine) -> Result<()> {
+        let role = match self.roles.role_of(id) {
+            Some(role) => role,
+            None => return Ok(()),
+        };
+        let roles = &engine.config.control.roles;
+        let in_control = [roles.motors, roles.master]
+            .iter()
+            .any(|&needed| needed == role || (needed == Role::CoPilot && role == Role::Pilot));
+        if !in_control {
+            return Ok(());
+        }
+
+        warn!("Gamepad disconnected; stopping the thrusters";
+              "gamepad" => id,
+              "role" => role.name());
+        flight.stop("gamepad disconnected")?;
+        self.lost_gamepads.push((id, role));
+        self.alert = Some(format!("{}'s gamepad disconnected", role.name()));
         Ok(())
     }
 }
 
+impl Drop for RovControl {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(control_thread) = self.control_thread.take() {
+            let _ = control_thread.join();
+        }
+    }
+}
+
+/// Out of date readings are drawn in grey, with their text flashing, so they aren't taken for live
+/// ones. Returns whether their text should be drawn this frame.
+fn show_stale(engine: &mut Engine, stale: bool) -> bool {
+    use sdl2::pixels::Color;
+    if stale {
+        engine.canvas.set_draw_color(Color::RGB(110, 110, 110));
+        ::time::precise_time_s() % 1.0 < 0.5
+    } else {
+        engine.canvas.set_draw_color(Color::RGB(255, 255, 255));
+        true
+    }
+}
+
 trait Renderable {
-    fn render(&self, mock: &MockRov, engine: &mut Engine);
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine);
 }
 
 struct MotorRenderable {
@@ -185,7 +366,7 @@ impl MotorRenderable {
 }
 
 impl Renderable for MotorRenderable {
-    fn render(&self, mock: &MockRov, engine: &mut Engine) {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
         use vecmath::{vec2_add, vec2_mul, vec2_normalized, vec2_scale, vec2_sub, vec2_len};
         let motor_start = vec2_add(self.max_pos, self.min_pos);
         let motor_start = vec2_mul(motor_start, [0.5, 0.5]);
@@ -193,8 +374,9 @@ impl Renderable for MotorRenderable {
         let motor_direction = vec2_normalized(forward_vector);
         let motor_len = vec2_len(forward_vector);
 
-        let value = mock.motors[self.id as usize] as f32;
-        let value = value / (i16::max_value() as f32);
+        let motor = vehicle.motors[self.id as usize];
+        show_stale(engine, motor.is_stale());
+        let value = motor.value as f32 / (i16::max_value() as f32);
 
         let amount = motor_len * value;
 
@@ -225,7 +407,7 @@ impl ServoRenderable {
 }
 
 impl Renderable for ServoRenderable {
-    fn render(&self, mock: &MockRov, engine: &mut Engine) {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
         use vecmath::{vec2_add, vec2_mul, vec2_normalized, vec2_scale, vec2_sub, vec2_len};
         let servo_start = vec2_add(self.max_pos, self.min_pos);
         let servo_start = vec2_mul(servo_start, [0.5, 0.5]);
@@ -236,8 +418,9 @@ impl Renderable for ServoRenderable {
         let servo_low = ::control::servo::SERVO_LOW as f32;
         let servo_high = ::control::servo::SERVO_HIGH as f32;
 
-        let value = mock.servos[self.id as usize] as f32;
-        let value = (value - servo_low) / (servo_high - servo_low);
+        let servo = vehicle.servos[self.id as usize];
+        show_stale(engine, servo.is_stale());
+        let value = (servo.value as f32 - servo_low) / (servo_high - servo_low);
 
         let amount = servo_len * value;
 
@@ -268,15 +451,18 @@ impl DualServoRenderable {
 }
 
 impl Renderable for DualServoRenderable {
-    fn render(&self, mock: &MockRov, engine: &mut Engine) {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
         let servo_low = ::control::servo::SERVO_LOW as f32;
         let servo_high = ::control::servo::SERVO_HIGH as f32;
 
-        let value = mock.servos[self.ids[0] as usize] as f32;
+        let servos = [vehicle.servos[self.ids[0] as usize], vehicle.servos[self.ids[1] as usize]];
+        show_stale(engine, servos.iter().any(|servo| servo.is_stale()));
+
+        let value = servos[0].value as f32;
         let x = (value - servo_low) / (servo_high - servo_low);
         let x = x * (self.max_pos[0] - self.min_pos[0]) + self.min_pos[0];
 
-        let value = mock.servos[self.ids[1] as usize] as f32;
+        let value = servos[1].value as f32;
         let y = (value - servo_low) / (servo_high - servo_low);
         let y = y * (self.max_pos[1] - self.min_pos[1]) + self.min_pos[1];
 
@@ -298,7 +484,8 @@ impl CompassRenderable {
 use rusttype::Scale;
 
 impl Renderable for CompassRenderable {
-    fn render(&self, mock: &MockRov, engine: &mut Engine) {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
+        let show_text = show_stale(engine, vehicle.compass.is_stale());
         let rect = (self.top_left[0], self.top_left[1], 200, 200).into();
         engine.canvas.draw_rect(rect).unwrap();
 
@@ -307,17 +494,163 @@ impl Renderable for CompassRenderable {
 
         engine.queue_text(x, y - 10.0, Scale::uniform(50.0), "Compass");
 
-        if mock.compass_enabled {
-            let x_string = format!("X: {}", (mock.compass_orientation[0] as f32) / 100.0);
+        if !show_text {
+            return;
+        }
+        if let Some(compass) = vehicle.compass.value {
+            let (heading, roll, pitch) = ::vehicle::orientation_degrees(compass);
+            let x_string = format!("X: {}", heading);
             engine.queue_text(x + 10.0, y + 40.0, Scale::uniform(32.0), &x_string);
 
-            let y_string = format!("Y: {}", (mock.compass_orientation[1] as f32) / 100.0);
+            let y_string = format!("Y: {}", roll);
             engine.queue_text(x + 10.0, y + 72.0, Scale::uniform(32.0), &y_string);
 
-            let z_string = format!("Z: {}", (mock.compass_orientation[2] as f32) / 100.0);
+            let z_string = format!("Z: {}", pitch);
             engine.queue_text(x + 10.0, y + 104.0, Scale::uniform(32.0), &z_string);
         } else {
             engine.queue_text(x + 10.0, y + 50.0, Scale::uniform(32.0), "Not Found");
         }
     }
 }
+
+struct DepthRenderable {
+    pub top_left: [i32; 2],
+}
+
+impl DepthRenderable {
+    pub fn new(top_left: [i32; 2]) -> Self {
+        DepthRenderable { top_left: top_left }
+    }
+}
+
+impl Renderable for DepthRenderable {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
+        let show_text = show_stale(engine, vehicle.pressure.is_stale());
+        let rect = (self.top_left[0], self.top_left[1], 160, 100).into();
+        engine.canvas.draw_rect(rect).unwrap();
+
+        let x = self.top_left[0] as f32;
+        let y = self.top_left[1] as f32;
+
+        engine.queue_text(x, y - 10.0, Scale::uniform(50.0), "Depth");
+
+        if !show_text {
+            return;
+        }
+        if let Some(depth) = vehicle.depth() {
+            let depth_string = format!("{:.2} m", depth);
+            engine.queue_text(x + 10.0, y + 50.0, Scale::uniform(32.0), &depth_string);
+        } else {
+            engine.queue_text(x + 10.0, y + 50.0, Scale::uniform(32.0), "Not Found");
+        }
+    }
+}
+
+/// A bar filled to the brightness the ROV reports for a dimmable channel.
+struct LightRenderable {
+    pub id: u8,
+    pub top_left: [i32; 2],
+}
+
+impl LightRenderable {
+    pub fn new(id: u8, top_left: [i32; 2]) -> Self {
+        LightRenderable {
+            id: id,
+            top_left: top_left,
+        }
+    }
+}
+
+impl Renderable for LightRenderable {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
+        let light = match vehicle.lights.get(self.id as usize) {
+            Some(&light) => light,
+            None => return,
+        };
+        let show_text = show_stale(engine, light.is_stale());
+        let (x, y) = (self.top_left[0], self.top_left[1]);
+        engine.canvas.draw_rect((x, y, 20, 80).into()).unwrap();
+        let height = light.value as i32 * 80 / 255;
+        if height > 0 {
+            let rect = (x, y + 80 - height, 20, height as u32).into();
+            engine.canvas.fill_rect(Some(rect)).unwrap();
+        }
+        if show_text {
+            let label = format!("L{}", self.id);
+            engine.queue_text(x as f32, y as f32 + 80.0, Scale::uniform(24.0), &label);
+        }
+    }
+}
+
+struct ManipulatorRenderable {
+    pub top_left: [i32; 2],
+    pub mode: ::control::manipulator::Mode,
+    pub jaw: u8,
+    pub open: i16,
+    pub closed: i16,
+}
+
+impl ManipulatorRenderable {
+    pub fn new(config: &::config::Manipulator, top_left: [i32; 2]) -> Self {
+        ManipulatorRenderable {
+            top_left: top_left,
+            mode: config.mode,
+            jaw: config.jaw,
+            open: config.open,
+            closed: config.closed,
+        }
+    }
+}
+
+impl Renderable for ManipulatorRenderable {
+    fn render(&self, vehicle: &VehicleState, engine: &mut Engine) {
+        use control::manipulator::Mode;
+        use control::servo::SERVO_MID;
+
+        let jaw = match vehicle.servos.get(self.jaw as usize) {
+            Some(&jaw) => jaw,
+            None => return,
+        };
+        let show_text = show_stale(engine, jaw.is_stale());
+        let rect = (self.top_left[0], self.top_left[1], 160, 100).into();
+        engine.canvas.draw_rect(rect).unwrap();
+
+        let x = self.top_left[0];
+        let y = self.top_left[1];
+
+        engine.queue_text(x as f32, y as f32 - 10.0, Scale::uniform(50.0), "Gripper");
+
+        let microseconds = jaw.value;
+        match self.mode {
+            Mode::Position => {
+                // Two jaws, as far apart as the echoed position is open. Pulse widths are widened
+                // first, as a bad echo could overflow them
+                let travel = self.open as i32 - self.closed as i32;
+                let open = if travel == 0 {
+                    1.0
+                } else {
+                    (microseconds as i32 - self.closed as i32) as f32 / travel as f32
+                };
+                let gap = (open.max(0.0).min(1.0) * 50.0) as i32;
+                let centre = x + 80;
+                engine.canvas.fill_rect(Some((centre - gap - 10, y + 50, 10, 40).into())).unwrap();
+                engine.canvas.fill_rect(Some((centre + gap, y + 50, 10, 40).into())).unwrap();
+            }
+            Mode::Velocity => {
+                let closing = (microseconds - SERVO_MID).signum() ==
+                              (self.closed - SERVO_MID).signum();
+                let state = if microseconds == SERVO_MID {
+                    "Stopped"
+                } else if closing {
+                    "Closing"
+                } else {
+                    "Opening"
+                };
+                if show_text {
+                    let (x, y) = (x as f32 + 10.0, y as f32 + 50.0);
+                    engine.queue_text(x, y, Scale::uniform(32.0), state);
+                }
+            }
+        }
+    }
+}
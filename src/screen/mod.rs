@@ -1,6 +1,8 @@
 
 pub mod control_rov;
 pub mod port_select;
+pub mod motor_bench;
+pub mod panel;
 
 use ::errors::*;
 
@@ -17,6 +19,7 @@ pub trait Screen {
 }
 
 use sdl2::EventPump;
+use sdl2::pixels::Color;
 use sdl2::render::{WindowCanvas, Texture};
 use rusttype::{Font, PositionedGlyph};
 use rusttype::gpu_cache::Cache;
@@ -29,7 +32,8 @@ pub struct Engine<'app> {
     pub canvas: WindowCanvas,
     pub rfont: Font<'app>,
     pub cache: Cache,
-    pub glyphs: Vec<PositionedGlyph<'app>>,
+    /// Text waiting to be drawn, in the draw colour it was queued with
+    pub glyphs: Vec<(PositionedGlyph<'app>, Color)>,
     pub cache_texture: Texture<'app>,
     pub config: Config,
 }
@@ -42,6 +46,7 @@ impl<'a> Engine<'a> {
         use rusttype::Point;
 
         let y = y + scale.y;
+        let color = self.canvas.draw_color();
         let mut caret = Point {x, y};
         for c in text.nfc() {
             let base_glyph = if let Some(glyph) = self.rfont.glyph(c) {
@@ -51,14 +56,14 @@ impl<'a> Engine<'a> {
             };
             let glyph = base_glyph.scaled(scale).positioned(caret);
             caret.x += glyph.unpositioned().h_metrics().advance_width;
-            self.glyphs.push(glyph.standalone());
+            self.glyphs.push((glyph.standalone(), color));
         }
     }
 
     pub fn render_text(&mut self) {
         use sdl2::rect::Rect as SdlRect;
 
-        for glyph in &self.glyphs {
+        for &(ref glyph, _) in &self.glyphs {
             self.cache.queue_glyph(0, glyph.clone());
         }
         let mut to_cache = vec![];
@@ -90,7 +95,7 @@ impl<'a> Engine<'a> {
 
         let (cache_width, cache_height) = self.cache.dimensions();
         let (cache_width, cache_height) = (cache_width as f32, cache_height as f32);
-        for glyph in &self.glyphs {
+        for &(ref glyph, color) in &self.glyphs {
             let cache_result = self.cache.rect_for(0, glyph)
                 .expect("Glyph not in cache");
             if let Some((src, dest)) = cache_result {
@@ -104,6 +109,7 @@ impl<'a> Engine<'a> {
                     dest.min.y as i32,
                     dest.width() as u32,
                     dest.height() as u32);
+                self.cache_texture.set_color_mod(color.r, color.g, color.b);
                 self.canvas.copy(
                     &self.cache_texture,
                     Some(cache_rect),
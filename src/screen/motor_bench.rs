@@ -0,0 +1,211 @@
+use rov::{Rov, RovCommand};
+use std::time::Instant;
+use vehicle::VehicleState;
+use screen::{Engine, Screen, Trans};
+use screen::control_rov::RovControl;
+use control::bench::{Pulse, Pulses};
+use rusttype::Scale;
+use ::errors::*;
+
+/// Checks each motor on the bench before a dive by pulsing it and watching what the ROV echoes.
+///
+/// Up and down select a motor and left or right pulse it in reverse or forward. Space runs every
+/// motor forward then in reverse, one at a time, and any other key stops the run. `M` switches
+/// master power, which the motors need. Return goes on to fly the ROV and escape quits; both stop
+/// the motors and switch master power off first.
+pub struct MotorBench {
+    rov: Option<Rov>,
+    vehicle: VehicleState,
+    /// The motors the thrusters drive, as configured
+    motor_ids: Vec<u8>,
+    selected: usize,
+    pulses: Pulses,
+    /// Raw throttle last sent to each motor, to compare with the echo
+    commanded: Vec<i16>,
+    master_on: bool,
+    /// When master power was last switched on here
+    master_switched: Instant,
+}
+
+impl MotorBench {
+    pub fn new(rov: Rov) -> MotorBench {
+        MotorBench {
+            rov: Some(rov),
+            vehicle: VehicleState::new(&::config::Staleness::default()),
+            motor_ids: vec![],
+            selected: 0,
+            pulses: Pulses::new(),
+            commanded: vec![],
+            master_on: false,
+            master_switched: Instant::now(),
+        }
+    }
+
+    fn send(&mut self, commands: Vec<RovCommand>) -> Result<()> {
+        let rov = match self.rov {
+            Some(ref mut rov) => rov,
+            None => return Ok(()),
+        };
+        for command in commands {
+            if let RovCommand::ControlMotor { id, throttle } = command {
+                if let Some(index) = self.motor_ids.iter().position(|&motor| motor == id) {
+                    self.commanded[index] = throttle;
+                }
+            }
+            rov.send_command(command).chain_err(|| "Failed to command the bench")?;
+        }
+        Ok(())
+    }
+
+    /// Stop every motor and switch master power off, ready to leave the bench.
+    fn shut_down(&mut self) -> Result<()> {
+        let mut commands = vec![];
+        self.pulses.abort(&mut commands);
+        for &id in self.motor_ids.iter() {
+            commands.push(RovCommand::ControlMotor {
+                id: id,
+                throttle: 0,
+            });
+        }
+        commands.push(RovCommand::MasterOff);
+        self.master_on = false;
+        self.send(commands)
+    }
+
+    fn key_down(&mut self, keycode: ::sdl2::keyboard::Keycode, engine: &Engine) -> Result<()> {
+        use sdl2::keyboard::Keycode;
+
+        let bench = &engine.config.bench;
+        let mut commands = vec![];
+        if self.pulses.remaining() > 0 {
+            info!("Bench sequence stopped");
+            self.pulses.abort(&mut commands);
+            return self.send(commands);
+        }
+        match keycode {
+            Keycode::Down if self.selected + 1 < self.motor_ids.len() => self.selected += 1,
+            Keycode::Up if self.selected > 0 => self.selected -= 1,
+            Keycode::Left | Keycode::Right => {
+                let direction = if keycode == Keycode::Right { 1.0 } else { -1.0 };
+                self.pulses.push(Pulse {
+                    id: self.motor_ids[self.selected],
+                    thrust: bench.thrust * direction,
+                    duration: bench.pulse_time,
+                });
+            }
+            Keycode::Space => {
+                info!("Bench sequence started");
+                self.pulses.sequence(&self.motor_ids, bench.thrust, bench.pulse_time);
+            }
+            Keycode::M => {
+                self.master_on = !self.master_on;
+                self.master_switched = Instant::now();
+                commands.push(if self.master_on {
+                    RovCommand::MasterOn
+                } else {
+                    RovCommand::MasterOff
+                });
+            }
+            _ => {}
+        }
+        self.send(commands)
+    }
+}
+
+impl Screen for MotorBench {
+    fn init(&mut self, engine: &mut Engine) -> Result<()> {
+        self.vehicle = VehicleState::new(&engine.config.staleness);
+        self.motor_ids = ::flight::motor_ids(&engine.config.control);
+        self.commanded = vec![0; self.motor_ids.len()];
+        self.send(vec![RovCommand::MasterOff])
+    }
+
+    fn update(&mut self, engine: &mut Engine, delta: f64) -> Result<Trans> {
+        let mut keys = vec![];
+        for event in engine.event_pump.poll_iter() {
+            use sdl2::event::Event;
+            use sdl2::keyboard::Keycode;
+
+            match event {
+                Event::Quit { .. } |
+                Event::KeyUp { keycode: Some(Keycode::Escape), .. } => {
+                    self.shut_down()?;
+                    return Ok(Trans::Quit);
+                }
+                Event::KeyDown { keycode: Some(Keycode::Return), .. } => {
+                    self.shut_down()?;
+                    if let Some(rov) = self.rov.take() {
+                        return Ok(Trans::Switch(Box::new(RovControl::new(rov))));
+                    }
+                }
+                Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => keys.push(keycode),
+                _ => (),
+            }
+        }
+        for keycode in keys {
+            self.key_down(keycode, engine)?;
+        }
+        // Gamepads are not used here, but their events still need taking
+        for _ in engine.controllers.poll_events() {}
+
+        let mut commands = vec![];
+        self.pulses.update(delta, &mut commands);
+        self.send(commands)?;
+
+        if let Some(ref mut rov) = self.rov {
+            let responses = rov.responses();
+            self.vehicle.apply_responses(&responses);
+        }
+        let master = &self.vehicle.master;
+        let reported = master.received.map_or(false, |received| received >= self.master_switched);
+        if self.master_on && reported && !master.value {
+            // The ROV switched itself off, so there is no point running motors
+            let mut commands = vec![];
+            self.pulses.abort(&mut commands);
+            self.master_on = false;
+            self.send(commands)?;
+        }
+
+        Ok(Trans::None)
+    }
+
+    fn render(&mut self, engine: &mut Engine, _delta: f64) -> Result<()> {
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), "Motor bench");
+        let line = format!("Master {}; pulses of {:.2} for {:.1} s",
+                           if self.vehicle.master.value { "on" } else { "off" },
+                           engine.config.bench.thrust,
+                           engine.config.bench.pulse_time);
+        engine.queue_text(30.0, 70.0, Scale::uniform(32.0), &line);
+        if !self.vehicle.master.value {
+            engine.queue_text(30.0, 102.0, Scale::uniform(32.0), "Press M for master power");
+        }
+
+        let running = self.pulses.current();
+        let mut y = 160.0;
+        for (index, &id) in self.motor_ids.iter().enumerate() {
+            // The vehicle only keeps echoes for the motors the firmware can drive
+            let echoed = self.vehicle
+                .motors
+                .get(id as usize)
+                .map_or("-".to_string(), |motor| motor.value.to_string());
+            let line = format!("Motor {}: sent {:>6}  echoed {:>6}",
+                               id,
+                               self.commanded[index],
+                               echoed);
+            engine.queue_text(60.0, y, Scale::uniform(28.0), &line);
+            if index == self.selected {
+                engine.queue_text(30.0, y, Scale::uniform(28.0), ">");
+            }
+            if running.map_or(false, |pulse| pulse.id == id && pulse.thrust != 0.0) {
+                engine.queue_text(560.0, y, Scale::uniform(28.0), "running");
+            }
+            y += 36.0;
+        }
+
+        if self.pulses.remaining() > 0 {
+            let line = format!("{} pulses left; any key stops", self.pulses.remaining());
+            engine.queue_text(60.0, y + 20.0, Scale::uniform(32.0), &line);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use input::{Axis, InputState};
+use input::roles::Role;
+use screen::Engine;
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Point;
+use rusttype::Scale;
+
+const STICK_SIZE: i32 = 200;
+const TRIGGER_HEIGHT: i32 = 200;
+
+/// Shows a role's axes before and after shaping. Raw values are drawn as outlines and shaped
+/// values filled in. Left or right switch between the pilot and co-pilot.
+pub struct InputsPanel {
+    pub role: Role,
+}
+
+impl InputsPanel {
+    pub fn new() -> InputsPanel {
+        InputsPanel { role: Role::Pilot }
+    }
+
+    pub fn key_down(&mut self, keycode: Keycode) {
+        match keycode {
+            Keycode::Left | Keycode::Right => {
+                self.role = match self.role {
+                    Role::Pilot => Role::CoPilot,
+                    _ => Role::Pilot,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, raw: &InputState, shaped: &InputState, engine: &mut Engine) {
+        let title = format!("Inputs: {}", self.role.name());
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), &title);
+
+        let shaping = engine.config.control.shaping.clone();
+        render_stick(engine,
+                     [60, 100],
+                     shaping.left_stick.deadzone,
+                     [raw.value(Axis::LeftStickX), raw.value(Axis::LeftStickY)],
+                     [shaped.value(Axis::LeftStickX), shaped.value(Axis::LeftStickY)]);
+        render_stick(engine,
+                     [300, 100],
+                     shaping.right_stick.deadzone,
+                     [raw.value(Axis::RightStickX), raw.value(Axis::RightStickY)],
+                     [shaped.value(Axis::RightStickX), shaped.value(Axis::RightStickY)]);
+        render_trigger(engine,
+                       [560, 100],
+                       raw.value(Axis::LeftTrigger2),
+                       shaped.value(Axis::LeftTrigger2));
+        render_trigger(engine,
+                       [620, 100],
+                       raw.value(Axis::RightTrigger2),
+                       shaped.value(Axis::RightTrigger2));
+
+        let axes = [("LX", Axis::LeftStickX),
+                    ("LY", Axis::LeftStickY),
+                    ("RX", Axis::RightStickX),
+                    ("RY", Axis::RightStickY),
+                    ("LT", Axis::LeftTrigger2),
+                    ("RT", Axis::RightTrigger2)];
+        let mut y = 320.0;
+        for &(name, axis) in axes.iter() {
+            let line = format!("{}: {:+.2} -> {:+.2}", name, raw.value(axis), shaped.value(axis));
+            engine.queue_text(60.0, y, Scale::uniform(28.0), &line);
+            y += 28.0;
+        }
+    }
+}
+
+fn render_stick(engine: &mut Engine,
+                top_left: [i32; 2],
+                deadzone: f32,
+                raw: [f32; 2],
+                shaped: [f32; 2]) {
+    let half = STICK_SIZE / 2;
+    let center = [top_left[0] + half, top_left[1] + half];
+    engine.canvas
+        .draw_rect((top_left[0], top_left[1], STICK_SIZE as u32, STICK_SIZE as u32).into())
+        .unwrap();
+
+    let radius = deadzone * half as f32;
+    let circle: Vec<Point> = (0..33)
+        .map(|i| {
+            let angle = i as f32 / 32.0 * 2.0 * ::std::f32::consts::PI;
+            Point::new(center[0] + (angle.cos() * radius) as i32,
+                       center[1] + (angle.sin() * radius) as i32)
+        })
+        .collect();
+    engine.canvas.draw_lines(circle.as_slice()).unwrap();
+
+    // Up on the stick is positive, but down is positive on the screen
+    let to_screen = |value: [f32; 2]| {
+        [center[0] + (value[0] * half as f32) as i32, center[1] - (value[1] * half as f32) as i32]
+    };
+    let raw = to_screen(raw);
+    engine.canvas.draw_rect((raw[0] - 6, raw[1] - 6, 12, 12).into()).unwrap();
+    let shaped = to_screen(shaped);
+    engine.canvas.fill_rect(Some((shaped[0] - 4, shaped[1] - 4, 8, 8).into())).unwrap();
+}
+
+fn render_trigger(engine: &mut Engine, top_left: [i32; 2], raw: f32, shaped: f32) {
+    let bottom = top_left[1] + TRIGGER_HEIGHT;
+    let raw_height = (raw.abs() * TRIGGER_HEIGHT as f32) as i32;
+    let shaped_height = (shaped.abs() * TRIGGER_HEIGHT as f32) as i32;
+    engine.canvas
+        .draw_rect((top_left[0], top_left[1], 40, TRIGGER_HEIGHT as u32).into())
+        .unwrap();
+    if raw_height > 0 {
+        engine.canvas
+            .draw_rect((top_left[0] + 5, bottom - raw_height, 30, raw_height as u32).into())
+            .unwrap();
+    }
+    if shaped_height > 0 {
+        engine.canvas
+            .fill_rect(Some((top_left[0] + 10, bottom - shaped_height, 20, shaped_height as u32)
+                .into()))
+            .unwrap();
+    }
+}
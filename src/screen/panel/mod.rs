@@ -0,0 +1,42 @@
+//! Panels drawn in place of the HUD on the flight screen. The vehicle keeps being controlled while
+//! a panel is open.
+
+pub mod inputs;
+pub mod motors;
+pub mod roles;
+pub mod servos;
+pub mod tuning;
+
+pub enum Panel {
+    Hud,
+    Roles(roles::RolesPanel),
+    Inputs(inputs::InputsPanel),
+    Tuning(tuning::TuningPanel),
+    Servos(servos::ServosPanel),
+    Motors(motors::MotorsPanel),
+}
+
+impl Panel {
+    /// The panel that `Tab` switches to.
+    pub fn next(&self) -> Panel {
+        match *self {
+            Panel::Hud => Panel::Roles(roles::RolesPanel::new()),
+            Panel::Roles(_) => Panel::Inputs(inputs::InputsPanel::new()),
+            Panel::Inputs(_) => Panel::Tuning(tuning::TuningPanel::new()),
+            Panel::Tuning(_) => Panel::Servos(servos::ServosPanel::new()),
+            Panel::Servos(_) => Panel::Motors(motors::MotorsPanel::new()),
+            Panel::Motors(_) => Panel::Hud,
+        }
+    }
+
+    /// Write anything the panel was asked to save to `config.toml`. Called once the flight has
+    /// been let go, so the control thread doesn't wait on the disk.
+    pub fn save(&mut self, config: &mut ::config::Config) {
+        match *self {
+            Panel::Tuning(ref mut panel) => panel.save(config),
+            Panel::Servos(ref mut panel) => panel.save(config),
+            Panel::Motors(ref mut panel) => panel.save(),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use control::motor::Calibration;
+use control::thrusters::Thrusters;
+use screen::Engine;
+use sdl2::keyboard::Keycode;
+use rusttype::Scale;
+
+/// Finds each motor's calibration by spinning one at a time.
+///
+/// Up and down select a motor, left and right set the test thrust, and holding space spins the
+/// motor while the vehicle is armed. `R` reverses it, `Q`/`A` raise or lower the forward scale,
+/// `E`/`D` the reverse scale and `]`/`[` the deadband. `S` saves the motor's calibration to
+/// `config.toml`.
+pub struct MotorsPanel {
+    selected: usize,
+    /// Thrust before calibration, in -1..1
+    pub thrust: f32,
+    /// The motor and calibration to write once the flight is let go
+    pending: Option<(u8, Calibration)>,
+    message: Option<String>,
+}
+
+impl MotorsPanel {
+    pub fn new() -> MotorsPanel {
+        MotorsPanel {
+            selected: 0,
+            thrust: 0.2,
+            pending: None,
+            message: None,
+        }
+    }
+
+    /// The motor to spin and its thrust, while spinning is asked for.
+    pub fn test(&self, spinning: bool) -> Option<(usize, f32)> {
+        if spinning {
+            Some((self.selected, self.thrust))
+        } else {
+            None
+        }
+    }
+
+    pub fn key_down(&mut self, keycode: Keycode, thrusters: &mut Thrusters) {
+        let count = thrusters.motors.len();
+        let motor = match thrusters.motors.get_mut(self.selected) {
+            Some(motor) => motor,
+            None => return,
+        };
+        let calibration = &mut motor.calibration;
+        self.message = None;
+        match keycode {
+            Keycode::Down if self.selected + 1 < count => self.selected += 1,
+            Keycode::Up if self.selected > 0 => self.selected -= 1,
+            Keycode::Left => self.thrust = (self.thrust - 0.05).max(-1.0),
+            Keycode::Right => self.thrust = (self.thrust + 0.05).min(1.0),
+            Keycode::R => calibration.reversed = !calibration.reversed,
+            Keycode::Q => calibration.forward_scale += 0.05,
+            Keycode::A => calibration.forward_scale = (calibration.forward_scale - 0.05).max(0.0),
+            Keycode::E => calibration.reverse_scale += 0.05,
+            Keycode::D => calibration.reverse_scale = (calibration.reverse_scale - 0.05).max(0.0),
+            Keycode::RightBracket => {
+                calibration.deadband = (calibration.deadband + 0.01).min(0.5)
+            }
+            Keycode::LeftBracket => {
+                calibration.deadband = (calibration.deadband - 0.01).max(0.0)
+            }
+            Keycode::S => self.pending = Some((motor.id, *calibration)),
+            _ => {}
+        }
+    }
+
+    /// Write the calibration `S` asked for, if any.
+    pub fn save(&mut self) {
+        use toml::Value;
+        let (id, calibration) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let values = [("reversed", Value::Boolean(calibration.reversed)),
+                      ("forward_scale", Value::Float(calibration.forward_scale as f64)),
+                      ("reverse_scale", Value::Float(calibration.reverse_scale as f64)),
+                      ("deadband", Value::Float(calibration.deadband as f64))];
+        let key = ("id", Value::Integer(id as i64));
+        let result = ::util::save_entry_to_config_file("config.toml",
+                                                       &["control", "motors"],
+                                                       key,
+                                                       &values);
+        self.message = Some(match result {
+            Ok(()) => "Saved to config.toml".into(),
+            Err(ref e) => {
+                let error_trace = ::util::get_error_trace(e);
+                warn!("Failed to save motor calibration"; "error_trace" => error_trace);
+                e.to_string()
+            }
+        });
+    }
+
+    pub fn render(&self, thrusters: &Thrusters, armed: bool, engine: &mut Engine) {
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), "Motor calibration");
+        let line = format!("Test thrust {:+.2}; hold space to spin", self.thrust);
+        engine.queue_text(30.0, 70.0, Scale::uniform(32.0), &line);
+        if !armed {
+            engine.queue_text(30.0, 102.0, Scale::uniform(32.0), "Arm the ROV to spin motors");
+        }
+
+        let mut y = 160.0;
+        for (index, motor) in thrusters.motors.iter().enumerate() {
+            let calibration = &motor.calibration;
+            let line = format!("Motor {}: {:>6}  {}fwd x{:.2}  rev x{:.2}  deadband {:.2}",
+                               motor.id,
+                               motor.thrust,
+                               if calibration.reversed { "reversed  " } else { "" },
+                               calibration.forward_scale,
+                               calibration.reverse_scale,
+                               calibration.deadband);
+            engine.queue_text(60.0, y, Scale::uniform(28.0), &line);
+            if index == self.selected {
+                engine.queue_text(30.0, y, Scale::uniform(28.0), ">");
+            }
+            y += 36.0;
+        }
+
+        if let Some(ref message) = self.message {
+            engine.queue_text(60.0, y + 20.0, Scale::uniform(32.0), message);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use input::roles::Roles;
+use screen::Engine;
+use sdl2::keyboard::Keycode;
+use rusttype::Scale;
+
+/// Lists the connected gamepads and their roles.
+///
+/// Up and down select a gamepad and left or right give it the next role. Pressing `Select` on a
+/// gamepad also moves it to the next role.
+pub struct RolesPanel {
+    selected: usize,
+}
+
+impl RolesPanel {
+    pub fn new() -> RolesPanel {
+        RolesPanel { selected: 0 }
+    }
+
+    pub fn key_down(&mut self, keycode: Keycode, roles: &mut Roles) {
+        let count = roles.gamepads().len();
+        match keycode {
+            Keycode::Down if self.selected + 1 < count => self.selected += 1,
+            Keycode::Up if self.selected > 0 => self.selected -= 1,
+            Keycode::Left | Keycode::Right if self.selected < count => {
+                let gamepad = roles.gamepads()[self.selected].0;
+                roles.cycle(gamepad);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, roles: &Roles, engine: &mut Engine) {
+        let height = 40.0;
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), "Gamepad roles");
+
+        if roles.gamepads().is_empty() {
+            engine.queue_text(60.0, 80.0, Scale::uniform(height), "No gamepads connected");
+        }
+
+        let mut y = 80.0;
+        for (index, &(id, role)) in roles.gamepads().iter().enumerate() {
+            let name = engine.controllers
+                .connected_gamepad(id)
+                .map(|gamepad| gamepad.name().to_string())
+                .unwrap_or_default();
+            let line = format!("{}: {} - {}", id, name, role.name());
+            engine.queue_text(60.0, y, Scale::uniform(height), &line);
+            if index == self.selected {
+                engine.queue_text(30.0, y, Scale::uniform(height), ">");
+            }
+            y += height;
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use control::servo::{Servo, SERVO_LOW, SERVO_HIGH, SERVO_MID};
+use rov::RovCommand;
+use screen::Engine;
+use sdl2::keyboard::Keycode;
+use rusttype::Scale;
+
+/// Names of the servos as built by the flight screen, and their sections under `[control]`.
+const SERVOS: [(&'static str, &'static str); 2] = [("Tilt", "servo_tilt"), ("Pan", "servo_pan")];
+
+/// How far each press of left or right moves the servo, in microseconds.
+const JOG: i16 = 10;
+
+/// Finds each servo's mechanical limits and centre.
+///
+/// Up and down select a servo and left or right move it past its current limits. `N` and `X`
+/// make the current position the minimum or maximum, `C` makes it the centre, and `S` saves the
+/// calibration to `config.toml`.
+pub struct ServosPanel {
+    selected: usize,
+    /// The servo and its (min, max, trim) to write once the flight is let go
+    pending: Option<(usize, (i16, i16, i16))>,
+    message: Option<String>,
+}
+
+impl ServosPanel {
+    pub fn new() -> ServosPanel {
+        ServosPanel {
+            selected: 0,
+            pending: None,
+            message: None,
+        }
+    }
+
+    /// A command to send straight away when the servo is moved.
+    pub fn key_down(&mut self, keycode: Keycode, servos: &mut [Servo]) -> Option<RovCommand> {
+        let servo = match servos.get_mut(self.selected) {
+            Some(servo) => servo,
+            None => return None,
+        };
+        self.message = None;
+        match keycode {
+            Keycode::Down if self.selected + 1 < SERVOS.len() => self.selected += 1,
+            Keycode::Up if self.selected > 0 => self.selected -= 1,
+            Keycode::Left | Keycode::Right => {
+                servo.jog(if keycode == Keycode::Right { JOG } else { -JOG });
+                return Some(RovCommand::ControlServo {
+                    id: servo.id,
+                    microseconds: servo.microseconds,
+                });
+            }
+            Keycode::N => servo.min = servo.microseconds.min(servo.max),
+            Keycode::X => servo.max = servo.microseconds.max(servo.min),
+            Keycode::C => servo.trim = servo.microseconds - SERVO_MID,
+            Keycode::S => {
+                self.pending = Some((self.selected, (servo.min, servo.max, servo.trim)))
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Write the calibration `S` asked for, if any.
+    pub fn save(&mut self, config: &mut ::config::Config) {
+        use toml::Value;
+        let (index, (min, max, trim)) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let (_, key) = SERVOS[index];
+        let values = [("min", Value::Integer(min as i64)),
+                      ("max", Value::Integer(max as i64)),
+                      ("trim", Value::Integer(trim as i64))];
+        let result = ::util::save_to_config_file("config.toml", &["control", key], &values);
+        self.message = Some(match result {
+            Ok(()) => {
+                let servo_config = if index == 0 {
+                    &mut config.control.servo_tilt
+                } else {
+                    &mut config.control.servo_pan
+                };
+                servo_config.min = min;
+                servo_config.max = max;
+                servo_config.trim = trim;
+                "Saved to config.toml".into()
+            }
+            Err(ref e) => {
+                let error_trace = ::util::get_error_trace(e);
+                warn!("Failed to save servo calibration"; "error_trace" => error_trace);
+                e.to_string()
+            }
+        });
+    }
+
+    pub fn render(&self, servos: &[Servo], engine: &mut Engine) {
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), "Servo calibration");
+
+        let mut y = 100;
+        for (index, (servo, &(name, _))) in servos.iter().zip(SERVOS.iter()).enumerate() {
+            let line = format!("{}: {} us  min {}  max {}  trim {:+}",
+                               name,
+                               servo.microseconds,
+                               servo.min,
+                               servo.max,
+                               servo.trim);
+            engine.queue_text(60.0, y as f32, Scale::uniform(32.0), &line);
+            if index == self.selected {
+                engine.queue_text(30.0, y as f32, Scale::uniform(32.0), ">");
+            }
+
+            // The whole travel, with the calibrated range filled in and a mark at the position
+            let to_x = |microseconds: i16| {
+                60 + (microseconds - SERVO_LOW) as i32 * 600 / (SERVO_HIGH - SERVO_LOW) as i32
+            };
+            engine.canvas.draw_rect((60, y + 20, 600, 30).into()).unwrap();
+            let range = (to_x(servo.min), y + 30, (to_x(servo.max) - to_x(servo.min)) as u32, 10);
+            engine.canvas.fill_rect(Some(range.into())).unwrap();
+            let position = to_x(servo.microseconds);
+            engine.canvas.draw_line((position, y + 15), (position, y + 55)).unwrap();
+            y += 120;
+        }
+
+        if let Some(ref message) = self.message {
+            engine.queue_text(60.0, y as f32, Scale::uniform(32.0), message);
+        }
+    }
+}
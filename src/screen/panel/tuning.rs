@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use control::hold::Hold;
+use control::thrusters::Thrusters;
+use screen::Engine;
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Point;
+use rusttype::Scale;
+
+/// How many control updates the traces cover.
+const TRACE_LENGTH: usize = 600;
+const TRACE_WIDTH: i32 = 700;
+const TRACE_HEIGHT: i32 = 150;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Loop {
+    Heading,
+    Depth,
+}
+
+impl Loop {
+    pub fn name(self) -> &'static str {
+        match self {
+            Loop::Heading => "Heading hold",
+            Loop::Depth => "Depth hold",
+        }
+    }
+
+    /// The loop's section under `[control]` in the config file.
+    pub fn config_key(self) -> &'static str {
+        match self {
+            Loop::Heading => "heading_hold",
+            Loop::Depth => "depth_hold",
+        }
+    }
+
+    fn hold(self, thrusters: &Thrusters) -> &Hold {
+        match self {
+            Loop::Heading => &thrusters.heading_hold,
+            Loop::Depth => &thrusters.depth_hold,
+        }
+    }
+
+    fn hold_mut(self, thrusters: &mut Thrusters) -> &mut Hold {
+        match self {
+            Loop::Heading => &mut thrusters.heading_hold,
+            Loop::Depth => &mut thrusters.depth_hold,
+        }
+    }
+}
+
+/// Traces a hold's target, measurement and output while its gains are adjusted.
+///
+/// Up and down select a gain and left or right lower or raise it by a tenth. Space switches
+/// between the loops and `S` saves the gains to `config.toml`. A gamepad's d-pad and `Select` do
+/// the same as the arrow keys and space.
+pub struct TuningPanel {
+    current: Loop,
+    selected: usize,
+    // (target, measurement, output)
+    trace: VecDeque<(Option<f32>, Option<f32>, f32)>,
+    /// The gains to write once the flight is let go
+    pending: Option<(Loop, ::config::Pid)>,
+    message: Option<String>,
+}
+
+impl TuningPanel {
+    pub fn new() -> TuningPanel {
+        TuningPanel {
+            current: Loop::Heading,
+            selected: 0,
+            trace: VecDeque::with_capacity(TRACE_LENGTH),
+            pending: None,
+            message: None,
+        }
+    }
+
+    /// Add the loop's latest state to the traces. Called on every control update.
+    pub fn record(&mut self, thrusters: &Thrusters) {
+        let hold = self.current.hold(thrusters);
+        if self.trace.len() == TRACE_LENGTH {
+            self.trace.pop_front();
+        }
+        self.trace.push_back((hold.target, hold.measurement, hold.output));
+    }
+
+    pub fn key_down(&mut self, keycode: Keycode, thrusters: &mut Thrusters) {
+        match keycode {
+            Keycode::Down if self.selected < 2 => self.selected += 1,
+            Keycode::Up if self.selected > 0 => self.selected -= 1,
+            Keycode::Left | Keycode::Right => {
+                let pid = &mut self.current.hold_mut(thrusters).pid;
+                let gain = match self.selected {
+                    0 => &mut pid.kp,
+                    1 => &mut pid.ki,
+                    _ => &mut pid.kd,
+                };
+                *gain = if keycode == Keycode::Right {
+                    if *gain == 0.0 { 0.001 } else { *gain * 1.1 }
+                } else {
+                    *gain / 1.1
+                };
+                self.message = None;
+            }
+            Keycode::Space => {
+                self.current = match self.current {
+                    Loop::Heading => Loop::Depth,
+                    Loop::Depth => Loop::Heading,
+                };
+                self.trace.clear();
+                self.message = None;
+            }
+            Keycode::S => {
+                let pid = ::config::Pid::from_loop(&self.current.hold(thrusters).pid);
+                self.pending = Some((self.current, pid));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn button_pressed(&mut self, button: ::gilrs::Button, thrusters: &mut Thrusters) {
+        use gilrs::Button;
+        let keycode = match button {
+            Button::DPadUp => Keycode::Up,
+            Button::DPadDown => Keycode::Down,
+            Button::DPadLeft => Keycode::Left,
+            Button::DPadRight => Keycode::Right,
+            Button::Select => Keycode::Space,
+            _ => return,
+        };
+        self.key_down(keycode, thrusters);
+    }
+
+    /// Write the gains `S` asked for, if any.
+    pub fn save(&mut self, config: &mut ::config::Config) {
+        let (current, pid) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let result = ::util::save_pid_to_file("config.toml", current.config_key(), &pid);
+        self.message = Some(match result {
+            Ok(()) => {
+                match current {
+                    Loop::Heading => config.control.heading_hold.pid = pid,
+                    Loop::Depth => config.control.depth_hold.pid = pid,
+                }
+                "Saved to config.toml".into()
+            }
+            Err(ref e) => {
+                let error_trace = ::util::get_error_trace(e);
+                warn!("Failed to save PID gains"; "error_trace" => error_trace);
+                e.to_string()
+            }
+        });
+    }
+
+    pub fn render(&self, thrusters: &Thrusters, engine: &mut Engine) {
+        let hold = self.current.hold(thrusters);
+        let title = format!("Tuning: {}", self.current.name());
+        engine.queue_text(30.0, 20.0, Scale::uniform(50.0), &title);
+
+        let gains = [("Kp", hold.pid.kp), ("Ki", hold.pid.ki), ("Kd", hold.pid.kd)];
+        let mut y = 70.0;
+        for (index, &(name, gain)) in gains.iter().enumerate() {
+            let line = format!("{}: {:.4}", name, gain);
+            engine.queue_text(60.0, y, Scale::uniform(32.0), &line);
+            if index == self.selected {
+                engine.queue_text(30.0, y, Scale::uniform(32.0), ">");
+            }
+            y += 32.0;
+        }
+        if !hold.enabled {
+            engine.queue_text(300.0, 70.0, Scale::uniform(32.0), "Hold is off");
+        }
+        if let Some(ref message) = self.message {
+            engine.queue_text(300.0, 102.0, Scale::uniform(32.0), message);
+        }
+
+        // Target and measurement share a scale that fits them both
+        let values = self.trace
+            .iter()
+            .flat_map(|&(target, measurement, _)| target.into_iter().chain(measurement));
+        let (low, high) = values.fold((::std::f32::MAX, ::std::f32::MIN),
+                                      |(low, high), value| (low.min(value), high.max(value)));
+        let (low, high) = if low < high {
+            (low, high)
+        } else if low == high {
+            (low - 1.0, high + 1.0)
+        } else {
+            (-1.0, 1.0)
+        };
+
+        let top = 180;
+        engine.queue_text(30.0,
+                          top as f32 - 5.0,
+                          Scale::uniform(24.0),
+                          "Target (dots) and measurement");
+        engine.canvas.draw_rect((30, top, TRACE_WIDTH as u32, TRACE_HEIGHT as u32).into()).unwrap();
+        let to_y = |value: f32| {
+            top + TRACE_HEIGHT - ((value - low) / (high - low) * TRACE_HEIGHT as f32) as i32
+        };
+        let targets: Vec<Point> = self.points(|sample| sample.0.map(&to_y));
+        engine.canvas.draw_points(targets.as_slice()).unwrap();
+        let measurements: Vec<Point> = self.points(|sample| sample.1.map(&to_y));
+        engine.canvas.draw_lines(measurements.as_slice()).unwrap();
+        let (high, low) = (format!("{:.1}", high), format!("{:.1}", low));
+        engine.queue_text(740.0, top as f32 + 20.0, Scale::uniform(24.0), &high);
+        engine.queue_text(740.0, (top + TRACE_HEIGHT) as f32, Scale::uniform(24.0), &low);
+
+        let top = 380;
+        let limit = hold.pid.output_limit.max(::std::f32::EPSILON);
+        engine.queue_text(30.0, top as f32 - 5.0, Scale::uniform(24.0), "Output");
+        engine.canvas.draw_rect((30, top, TRACE_WIDTH as u32, TRACE_HEIGHT as u32).into()).unwrap();
+        let middle = top + TRACE_HEIGHT / 2;
+        engine.canvas.draw_line((30, middle), (30 + TRACE_WIDTH, middle)).unwrap();
+        let outputs: Vec<Point> = self.points(|sample| {
+            Some(middle - (sample.2 / limit * (TRACE_HEIGHT / 2) as f32) as i32)
+        });
+        engine.canvas.draw_lines(outputs.as_slice()).unwrap();
+    }
+
+    /// Screen points for the samples that have a value, oldest on the left.
+    fn points<F>(&self, y: F) -> Vec<Point>
+        where F: Fn(&(Option<f32>, Option<f32>, f32)) -> Option<i32>
+    {
+        self.trace
+            .iter()
+            .enumerate()
+            .filter_map(|(index, sample)| {
+                let x = 30 + index as i32 * TRACE_WIDTH / TRACE_LENGTH as i32;
+                y(sample).map(|y| Point::new(x, y))
+            })
+            .collect()
+    }
+}
@@ -5,6 +5,7 @@ use time::{PreciseTime, Duration};
 use serialport;
 use screen::{Engine, Screen, Trans};
 use screen::control_rov::RovControl;
+use screen::motor_bench::MotorBench;
 use rov::Rov;
 use ::errors::*;
 
@@ -34,6 +35,11 @@ impl PortSelect {
             self.selected -= 1;
         }
     }
+
+    fn open_bench(&self) -> Trans {
+        let port = self.ports[self.selected].port_name.clone().into();
+        Trans::Switch(Box::new(MotorBench::new(Rov::new(port))))
+    }
 }
 
 impl Screen for PortSelect {
@@ -44,7 +50,7 @@ impl Screen for PortSelect {
     fn update(&mut self, engine: &mut Engine, _delta: f64) -> Result<Trans> {
         for (_id, event) in engine.controllers.poll_events() {
             use gilrs::Event::ButtonReleased as Press;
-            use gilrs::Button::{DPadUp, DPadDown, South};
+            use gilrs::Button::{DPadUp, DPadDown, South, West};
 
             match event {
                 Press(DPadDown, _) => self.select_next(),
@@ -57,6 +63,7 @@ impl Screen for PortSelect {
                         return Ok(Trans::Switch(control_screen));
                     }
                 }
+                Press(West, _) if self.ports.len() > 0 => return Ok(self.open_bench()),
                 _ => (),
             }
         }
@@ -75,6 +82,9 @@ impl Screen for PortSelect {
                         return Ok(Trans::Switch(control_screen));
                     }
                 }
+                Event::KeyDown { keycode: Some(Keycode::B), .. } if self.ports.len() > 0 => {
+                    return Ok(self.open_bench())
+                }
                 Event::Quit { .. } |
                 Event::KeyUp { keycode: Some(Keycode::Escape), .. } => return Ok(Trans::Quit),
                 _ => (),
@@ -2,6 +2,8 @@
 use ::errors::*;
 
 use config::Config;
+use std::time::Duration;
+
 pub fn load_config_from_file(path: &str) -> Result<Config> {
     use std::fs::File;
     use std::io::Read;
@@ -15,6 +17,172 @@ pub fn load_config_from_file(path: &str) -> Result<Config> {
     Ok(config)
 }
 
+/// Write values into a section of the config file, keeping the rest of its settings. The section
+/// is given as the path of table names leading to it.
+pub fn save_to_config_file(path: &str,
+                           section: &[&str],
+                           values: &[(&str, ::toml::Value)])
+                           -> Result<()> {
+    edit_config_file(path, |contents| set_values(contents, section, values))
+}
+
+/// Write a hold's PID gains into the config file.
+pub fn save_pid_to_file(path: &str, hold: &str, pid: &::config::Pid) -> Result<()> {
+    use toml::Value;
+    save_to_config_file(path,
+                        &["control", hold, "pid"],
+                        &[("kp", Value::Float(pid.kp as f64)),
+                          ("ki", Value::Float(pid.ki as f64)),
+                          ("kd", Value::Float(pid.kd as f64)),
+                          ("max_output", Value::Float(pid.max_output as f64))])
+}
+
+/// Write trim offsets into the config file, under the profile with the given name if there is
+/// one.
+pub fn save_trim_to_file(path: &str,
+                         profile: Option<&str>,
+                         offsets: &::config::TrimOffsets)
+                         -> Result<()> {
+    use toml::Value;
+    let values = [("heave", Value::Float(offsets.heave as f64)),
+                  ("pitch", Value::Float(offsets.pitch as f64)),
+                  ("roll", Value::Float(offsets.roll as f64))];
+    match profile {
+        Some(name) => {
+            let trim = values.iter().map(|&(key, ref value)| (key.to_string(), value.clone()));
+            save_entry_to_config_file(path,
+                                      &["control", "profiles"],
+                                      ("name", Value::String(name.into())),
+                                      &[("trim", Value::Table(trim.collect()))])
+        }
+        None => save_to_config_file(path, &["control", "trim", "offsets"], &values),
+    }
+}
+
+/// Write values into the entry of an array of tables in the config file, e.g. one of the
+/// `[[control.motors]]`, picked by a key such as its id. The entry is added if there isn't one.
+pub fn save_entry_to_config_file(path: &str,
+                                 array: &[&str],
+                                 key: (&str, ::toml::Value),
+                                 values: &[(&str, ::toml::Value)])
+                                 -> Result<()> {
+    edit_config_file(path, |contents| set_entry_values(contents, array, key, values))
+}
+
+/// Rewrite the config file, which is treated as empty if it doesn't exist yet.
+fn edit_config_file<F>(path: &str, edit: F) -> Result<()>
+    where F: FnOnce(&str) -> Result<String>
+{
+    use std::fs::File;
+    use std::io::{Read, Write};
+
+    let mut contents = String::new();
+    if let Ok(mut file) = File::open(path) {
+        file.read_to_string(&mut contents).chain_err(|| "Failed to read file")?;
+    }
+    let contents = edit(&contents)?;
+
+    let mut file = File::create(path).chain_err(|| "Failed to create config file")?;
+    file.write_all(contents.as_bytes()).chain_err(|| "Failed to write config file")?;
+
+    Ok(())
+}
+
+fn set_entry_values(contents: &str,
+                    array: &[&str],
+                    key: (&str, ::toml::Value),
+                    values: &[(&str, ::toml::Value)])
+                    -> Result<String> {
+    use toml::Value;
+
+    let (name, section) = match array.split_last() {
+        Some(split) => split,
+        None => bail!("No array given"),
+    };
+    let mut config: Value = contents.parse().chain_err(|| "Failed to parse config")?;
+    {
+        let mut table = &mut config;
+        for key in section.iter() {
+            table = match *table {
+                Value::Table(ref mut table) => {
+                    table.entry(key.to_string()).or_insert_with(|| Value::Table(Default::default()))
+                }
+                _ => bail!("Config has a value where a table was expected"),
+            };
+        }
+        let entries = match *table {
+            Value::Table(ref mut table) => {
+                table.entry(name.to_string()).or_insert_with(|| Value::Array(vec![]))
+            }
+            _ => bail!("Config has a value where a table was expected"),
+        };
+        let entries = match *entries {
+            Value::Array(ref mut entries) => entries,
+            _ => bail!("Config has a value where an array was expected"),
+        };
+        let (key, ref wanted) = key;
+        let position = entries.iter().position(|entry| entry.get(key) == Some(wanted));
+        let position = match position {
+            Some(position) => position,
+            None => {
+                let mut entry = ::toml::value::Table::new();
+                entry.insert(key.into(), wanted.clone());
+                entries.push(Value::Table(entry));
+                entries.len() - 1
+            }
+        };
+        match entries[position] {
+            Value::Table(ref mut entry) => {
+                for &(key, ref value) in values.iter() {
+                    entry.insert(key.to_string(), value.clone());
+                }
+            }
+            _ => bail!("Config has a value where a table was expected"),
+        }
+    }
+
+    ::toml::to_string(&config).chain_err(|| "Failed to serialize config")
+}
+
+fn set_values(contents: &str,
+              section: &[&str],
+              values: &[(&str, ::toml::Value)])
+              -> Result<String> {
+    use toml::Value;
+
+    let mut config: Value = contents.parse().chain_err(|| "Failed to parse config")?;
+    {
+        let mut table = &mut config;
+        for key in section.iter() {
+            table = match *table {
+                Value::Table(ref mut table) => {
+                    table.entry(key.to_string()).or_insert_with(|| Value::Table(Default::default()))
+                }
+                _ => bail!("Config has a value where a table was expected"),
+            };
+        }
+        match *table {
+            Value::Table(ref mut table) => {
+                for &(key, ref value) in values.iter() {
+                    table.insert(key.to_string(), value.clone());
+                }
+            }
+            _ => bail!("Config has a value where a table was expected"),
+        }
+    }
+
+    ::toml::to_string(&config).chain_err(|| "Failed to serialize config")
+}
+
+/// The time between ticks of something that runs at a rate in hertz.
+pub fn period(rate: f64) -> Duration {
+    Duration::new(0, (1_000_000_000.0 / rate.max(1.0)) as u32)
+}
+
+pub fn seconds(duration: Duration) -> f64 {
+    (duration.as_secs() as f64) + (duration.subsec_nanos() as f64 / 1_000_000_000.0)
+}
+
 pub fn get_error_trace(e: &Error) -> String {
     let mut error_trace = String::new();
     error_trace.push_str("Error: ");
@@ -25,3 +193,43 @@ pub fn get_error_trace(e: &Error) -> String {
     }
     error_trace
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_values_keep_other_settings() {
+        let contents = "[control.servo_pan]\nspeed = 2.0\n\n\
+                        [control.heading_hold]\nbutton = \"East\"\n";
+        let values = [("kd", ::toml::Value::Float(0.125))];
+        let contents = set_values(contents, &["control", "heading_hold", "pid"], &values).unwrap();
+
+        let config: ::toml::Value = contents.parse().unwrap();
+        let control = &config["control"];
+        assert_eq!(control["servo_pan"]["speed"].as_float(), Some(2.0));
+        assert_eq!(control["heading_hold"]["button"].as_str(), Some("East"));
+        assert_eq!(control["heading_hold"]["pid"]["kd"].as_float(), Some(0.125));
+    }
+
+    #[test]
+    fn saved_entry_is_found_by_id() {
+        use toml::Value;
+        let contents = "[[control.motors]]\nid = 0\nacceleration = 3.0\n\n\
+                        [[control.motors]]\nid = 2\n";
+        let values = [("reversed", Value::Boolean(true))];
+        let motors = ["control", "motors"];
+        let contents = set_entry_values(contents, &motors, ("id", Value::Integer(2)), &values)
+            .unwrap();
+        let contents = set_entry_values(&contents, &motors, ("id", Value::Integer(3)), &values)
+            .unwrap();
+
+        let config: ::toml::Value = contents.parse().unwrap();
+        let motors = config["control"]["motors"].as_array().unwrap();
+        assert_eq!(motors.len(), 3);
+        assert_eq!(motors[0]["acceleration"].as_float(), Some(3.0));
+        assert_eq!(motors[0].get("reversed"), None);
+        assert_eq!(motors[1]["reversed"].as_bool(), Some(true));
+        assert_eq!(motors[2]["id"].as_integer(), Some(3));
+    }
+}
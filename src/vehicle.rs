@@ -0,0 +1,325 @@
+/// What the ROV last reported about itself, and when.
+
+use rov::RovResponse;
+use std::collections::HashSet;
+use std::time::Instant;
+
+const GRAVITY: f32 = 9.80665;
+
+/// Heading, roll and pitch in degrees from the compass's hundredths of a degree. The firmware
+/// sends the heading, in 0..360, through a signed 16 bit number, so headings above 327.67° arrive
+/// wrapped around and are read back as unsigned.
+pub fn orientation_degrees(compass: [i16; 3]) -> (f32, f32, f32) {
+    (compass[0] as u16 as f32 / 100.0, compass[1] as f32 / 100.0, compass[2] as f32 / 100.0)
+}
+
+/// A value the ROV reports, with when it was received.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading<T> {
+    pub value: T,
+    /// None until the ROV first reports it
+    pub received: Option<Instant>,
+    /// Seconds after which the value is out of date, if it can go out of date
+    pub stale_after: Option<f64>,
+    /// Set when no more values are coming, e.g. from a sensor that reported itself disabled, so
+    /// the value can't go out of date
+    pub lasting: bool,
+}
+
+impl<T: Copy> Reading<T> {
+    pub fn new(value: T, stale_after: Option<f64>) -> Self {
+        Reading {
+            value: value,
+            received: None,
+            stale_after: stale_after,
+            lasting: false,
+        }
+    }
+
+    pub fn set(&mut self, value: T, now: Instant) {
+        self.value = value;
+        self.received = Some(now);
+        self.lasting = false;
+    }
+
+    /// Set a value that won't be sent again until it changes.
+    pub fn set_lasting(&mut self, value: T, now: Instant) {
+        self.set(value, now);
+        self.lasting = true;
+    }
+
+    /// Seconds since the value was received.
+    pub fn age_at(&self, now: Instant) -> Option<f64> {
+        self.received.map(|received| {
+            let age = now.duration_since(received);
+            age.as_secs() as f64 + age.subsec_nanos() as f64 / 1_000_000_000.0
+        })
+    }
+
+    /// A value that can go out of date is stale until it is first received.
+    pub fn is_stale_at(&self, now: Instant) -> bool {
+        if self.lasting {
+            return false;
+        }
+        match (self.stale_after, self.age_at(now)) {
+            (Some(limit), Some(age)) => age > limit,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.is_stale_at(Instant::now())
+    }
+
+    /// The value, unless it is stale.
+    pub fn current(&self) -> Option<T> {
+        if self.is_stale() {
+            None
+        } else {
+            Some(self.value)
+        }
+    }
+
+    pub fn sample_at(&self, now: Instant) -> Sample<T> {
+        Sample {
+            value: self.value,
+            age: self.age_at(now),
+            stale: self.is_stale_at(now),
+        }
+    }
+}
+
+/// A reading as it stood when a snapshot was taken.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Sample<T> {
+    pub value: T,
+    /// Seconds since it was received, if it has been
+    pub age: Option<f64>,
+    pub stale: bool,
+}
+
+/// A copy of the vehicle's state that can be logged or sent elsewhere.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub motors: Vec<Sample<i16>>,
+    pub servos: Vec<Sample<i16>>,
+    pub master: Sample<bool>,
+    pub light_relay: Sample<bool>,
+    pub lights: Vec<Sample<u8>>,
+    /// Heading, roll and pitch in degrees
+    pub compass: Sample<Option<(f32, f32, f32)>>,
+    /// In metres
+    pub depth: Sample<Option<f32>>,
+}
+
+impl Snapshot {
+    /// The names of the readings that are stale, e.g. "compass" or "motor 2".
+    pub fn stale(&self) -> Vec<String> {
+        let mut stale = vec![];
+        let groups = [("motor", &self.motors), ("servo", &self.servos)];
+        for &(name, samples) in groups.iter() {
+            for (id, sample) in samples.iter().enumerate() {
+                if sample.stale {
+                    stale.push(format!("{} {}", name, id));
+                }
+            }
+        }
+        for (id, sample) in self.lights.iter().enumerate() {
+            if sample.stale {
+                stale.push(format!("light {}", id));
+            }
+        }
+        let single = [("master", self.master.stale),
+                      ("light relay", self.light_relay.stale),
+                      ("compass", self.compass.stale),
+                      ("depth", self.depth.stale)];
+        for &(name, is_stale) in single.iter() {
+            if is_stale {
+                stale.push(name.into());
+            }
+        }
+        stale
+    }
+}
+
+pub struct VehicleState {
+    pub motors: [Reading<i16>; 6],
+    pub servos: [Reading<i16>; 4],
+    pub master: Reading<bool>,
+    pub light_relay: Reading<bool>,
+    /// Brightness of each dimmable channel
+    pub lights: [Reading<u8>; 2],
+    /// Heading, roll and pitch in hundredths of a degree, or None while the compass is disabled
+    pub compass: Reading<Option<[i16; 3]>>,
+    /// In pascals, or None while the depth sensor is disabled
+    pub pressure: Reading<Option<u32>>,
+    /// Density of the water the vehicle is in, in kg/m³
+    pub water_density: f32,
+    /// Pressure at the surface, in pascals
+    pub surface_pressure: f32,
+    pub i2c_devices: HashSet<u8>,
+}
+
+impl VehicleState {
+    pub fn new(staleness: &::config::Staleness) -> VehicleState {
+        VehicleState {
+            motors: [Reading::new(0, staleness.motors); 6],
+            // Start it at the middle
+            servos: [Reading::new(1500, staleness.servos); 4],
+            // Unknown until the ROV reports it, so it is shown off and out of date
+            master: Reading::new(false, staleness.master),
+            light_relay: Reading::new(false, staleness.lights),
+            lights: [Reading::new(0, staleness.lights); 2],
+            compass: Reading::new(None, staleness.compass),
+            pressure: Reading::new(None, staleness.pressure),
+            water_density: ::config::Water::Fresh.density(),
+            surface_pressure: ::config::STANDARD_ATMOSPHERE,
+            i2c_devices: HashSet::new(),
+        }
+    }
+
+    /// Metres below the surface, worked out from the last pressure reported.
+    pub fn depth(&self) -> Option<f32> {
+        self.pressure.value.map(|pascals| {
+            (pascals as f32 - self.surface_pressure) / (self.water_density * GRAVITY)
+        })
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let now = Instant::now();
+        let compass = self.compass.sample_at(now);
+        let pressure = self.pressure.sample_at(now);
+        Snapshot {
+            motors: self.motors.iter().map(|motor| motor.sample_at(now)).collect(),
+            servos: self.servos.iter().map(|servo| servo.sample_at(now)).collect(),
+            master: self.master.sample_at(now),
+            light_relay: self.light_relay.sample_at(now),
+            lights: self.lights.iter().map(|light| light.sample_at(now)).collect(),
+            compass: Sample {
+                value: self.compass.value.map(orientation_degrees),
+                age: compass.age,
+                stale: compass.stale,
+            },
+            depth: Sample {
+                value: self.depth(),
+                age: pressure.age,
+                stale: pressure.stale,
+            },
+        }
+    }
+
+    pub fn apply_responses(&mut self, responses: &Vec<RovResponse>) {
+        let now = Instant::now();
+        for response in responses.iter() {
+            self.apply_response(response, now);
+        }
+    }
+
+    pub fn apply_response(&mut self, response: &RovResponse, now: Instant) {
+        match *response {
+            RovResponse::Motor { id, throttle } => {
+                if let Some(motor) = self.motors.get_mut(id as usize) {
+                    motor.set(throttle, now);
+                }
+            }
+            RovResponse::CompassOrientation { x, y, z } => {
+                if self.compass.value.is_none() {
+                    warn!("Compass orientation received when disabled; enabling compass");
+                }
+                self.compass.set(Some([x, y, z]), now);
+            }
+            RovResponse::CompassDisabled => self.compass.set_lasting(None, now),
+            RovResponse::Pressure { pascals } => {
+                if self.pressure.value.is_none() {
+                    warn!("Pressure received when disabled; enabling depth sensor");
+                }
+                self.pressure.set(Some(pascals), now);
+            }
+            RovResponse::PressureDisabled => self.pressure.set_lasting(None, now),
+            RovResponse::LightsOn => self.light_relay.set(true, now),
+            RovResponse::LightsOff => self.light_relay.set(false, now),
+            RovResponse::Light { id, level } => {
+                if let Some(light) = self.lights.get_mut(id as usize) {
+                    light.set(level, now);
+                }
+            }
+            // Only reported when it switches
+            RovResponse::MasterOn => self.master.set_lasting(true, now),
+            RovResponse::MasterOff => self.master.set_lasting(false, now),
+            RovResponse::Servo { id, microseconds } => {
+                if let Some(servo) = self.servos.get_mut(id as usize) {
+                    servo.set(microseconds, now);
+                }
+            }
+            RovResponse::NoI2c => self.i2c_devices.clear(),
+            RovResponse::I2cFound { address, .. } => {
+                self.i2c_devices.insert(address);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn readings_go_stale_after_their_limit() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        let start = Instant::now();
+        assert!(vehicle.compass.is_stale_at(start));
+        assert!(!vehicle.motors[0].is_stale_at(start));
+
+        vehicle.apply_response(&RovResponse::CompassOrientation { x: 100, y: 0, z: 0 }, start);
+        vehicle.apply_response(&RovResponse::Motor { id: 1, throttle: 300 }, start);
+        assert!(!vehicle.compass.is_stale_at(start + Duration::from_millis(100)));
+        assert!(vehicle.compass.is_stale_at(start + Duration::from_secs(2)));
+        assert!(!vehicle.motors[1].is_stale_at(start + Duration::from_secs(600)));
+        assert_eq!(vehicle.compass.age_at(start + Duration::from_secs(2)), Some(2.0));
+    }
+
+    #[test]
+    fn disabled_sensors_are_not_stale() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        let start = Instant::now();
+        vehicle.apply_response(&RovResponse::CompassDisabled, start);
+        assert!(!vehicle.compass.is_stale_at(start + Duration::from_secs(600)));
+
+        vehicle.apply_response(&RovResponse::CompassOrientation { x: 100, y: 0, z: 0 }, start);
+        assert!(vehicle.compass.is_stale_at(start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn master_power_is_out_of_date_until_first_reported() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        let start = Instant::now();
+        assert!(!vehicle.master.value);
+        assert!(vehicle.master.is_stale_at(start));
+
+        vehicle.apply_response(&RovResponse::MasterOn, start);
+        assert!(vehicle.master.value);
+        assert!(!vehicle.master.is_stale_at(start + Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn headings_past_the_signed_range_are_unwrapped() {
+        let (heading, roll, pitch) = orientation_degrees([35900u16 as i16, -1250, 300]);
+        assert_eq!(heading, 359.0);
+        assert_eq!(roll, -12.5);
+        assert_eq!(pitch, 3.0);
+        assert_eq!(orientation_degrees([100, 0, 0]).0, 1.0);
+    }
+
+    #[test]
+    fn snapshot_names_what_is_stale() {
+        let mut vehicle = VehicleState::new(&::config::Staleness::default());
+        vehicle.apply_response(&RovResponse::Pressure { pascals: 111131 },
+                               Instant::now());
+        let snapshot = vehicle.snapshot();
+        assert_eq!(snapshot.stale(), vec!["master".to_string(), "compass".to_string()]);
+        assert!((snapshot.depth.value.unwrap() - 1.0).abs() < 0.01);
+        assert_eq!(snapshot.motors.len(), 6);
+    }
+}